@@ -1,7 +1,7 @@
 import { Router } from 'express';
 import { exec } from 'child_process';
 import { promisify } from 'util';
-import { readFile, unlink } from 'fs/promises';
+import { readFile, unlink, writeFile } from 'fs/promises';
 import path from 'path';
 import { blockchain } from '../services/blockchain.js';
 
@@ -13,17 +13,50 @@ const STEAM_ZKTLS_PATH = path.resolve(process.cwd(), '../steam-zktls');
 const BINARIES_PATH = path.join(STEAM_ZKTLS_PATH, 'target/release');
 
 // POST /api/verifier/submit-proof - Submit proof directly to verifier contract
-// For use with pre-generated proofs
+// For use with pre-generated proofs: either an exported `proof`, or a `presentation`
+// (base64/hex JSON wrapper from `present --encoding`, or a bare base64/hex string)
 router.post('/submit-proof', async (req, res) => {
   try {
-    const { tradeId, proof } = req.body;
+    const { tradeId, proof, presentation } = req.body;
 
     if (tradeId === undefined) {
       return res.status(400).json({ error: 'Missing tradeId' });
     }
 
-    if (!proof) {
-      return res.status(400).json({ error: 'Missing proof data' });
+    if (!proof && !presentation) {
+      return res.status(400).json({ error: 'Missing proof or presentation data' });
+    }
+
+    if (presentation) {
+      // tradeId ends up in file names
+      if (!Number.isInteger(Number(tradeId))) {
+        return res.status(400).json({ error: 'Invalid tradeId' });
+      }
+
+      // export checks the proof is for the trade's game, and detects the encoding itself
+      const trade = await blockchain.getTrade(tradeId);
+      const presentationFile = path.join(STEAM_ZKTLS_PATH, `presentation_${Number(tradeId)}.json`);
+      const proofFile = path.join(STEAM_ZKTLS_PATH, `proof_${Number(tradeId)}.json`);
+      const contents = typeof presentation === 'string' ? presentation : JSON.stringify(presentation);
+
+      try {
+        await writeFile(presentationFile, contents);
+        await execAsync(
+          `${BINARIES_PATH}/export -a ${trade.steamAppId} -i "${presentationFile}" -o "${proofFile}"`,
+          { cwd: STEAM_ZKTLS_PATH, timeout: 30000 }
+        );
+      } catch (e) {
+        console.error('Export failed:', e);
+        return res.status(400).json({ error: 'Invalid presentation', details: e.message });
+      } finally {
+        await unlink(presentationFile).catch(() => {});
+      }
+
+      const proofData = JSON.parse(await readFile(proofFile, 'utf-8'));
+      await unlink(proofFile).catch(() => {});
+
+      const txHash = await blockchain.submitPackedProofToVerifier(tradeId, proofData.packedProof);
+      return res.json({ success: true, txHash, ownsGame: proofData.ownsGame });
     }
 
     // Submit proof to the SteamGameVerifier contract
@@ -58,11 +91,33 @@ router.post('/verify', async (req, res) => {
     console.log(`  Steam Username: ${steamUsername}`);
     console.log(`  Steam App ID: ${steamAppId}`);
 
+    // Step 0: Pre-flight check, so a wrong name or private profile fails before the MPC session
+    console.log('Step 0: Pre-flight check...');
+    try {
+      await execAsync(
+        `${BINARIES_PATH}/prover -v "${steamUsername}" -a ${steamAppId} --dry-run`,
+        { cwd: STEAM_ZKTLS_PATH, timeout: 15000 }
+      );
+    } catch (e) {
+      let report;
+      try {
+        report = JSON.parse(e.stdout);
+      } catch {
+        report = undefined;
+      }
+      console.error('Pre-flight check failed:', report ?? e);
+      return res.status(400).json({
+        error: 'Pre-flight check failed',
+        details: report?.outcome ?? e.message,
+        report
+      });
+    }
+
     // Step 1: Run prover to generate attestation
     console.log('Step 1: Running prover...');
     try {
       await execAsync(
-        `${BINARIES_PATH}/prover -v "${steamUsername}" -a ${steamAppId}`,
+        `${BINARIES_PATH}/prover -v "${steamUsername}" -a ${steamAppId} --trade-id ${Number(tradeId)}`,
         { cwd: STEAM_ZKTLS_PATH, timeout: 60000 }
       );
     } catch (e) {
@@ -91,7 +146,7 @@ router.post('/verify', async (req, res) => {
     const proofFile = path.join(STEAM_ZKTLS_PATH, `proof_${tradeId}.json`);
     try {
       await execAsync(
-        `${BINARIES_PATH}/export -o "${proofFile}"`,
+        `${BINARIES_PATH}/export -a ${steamAppId} -o "${proofFile}"`,
         { cwd: STEAM_ZKTLS_PATH, timeout: 30000 }
       );
     } catch (e) {
@@ -104,20 +159,8 @@ router.post('/verify', async (req, res) => {
     const proofJson = await readFile(proofFile, 'utf-8');
     const proofData = JSON.parse(proofJson);
 
-    // Format proof for contract call
-    const proof = {
-      messageHash: proofData.messageHash,
-      v: proofData.signatureV,
-      r: proofData.signatureR,
-      s: proofData.signatureS,
-      serverName: proofData.serverName,
-      timestamp: proofData.timestamp,
-      ownsGame: proofData.ownsGame,
-      transcriptHash: proofData.transcriptHash
-    };
-
-    // Submit to verifier contract
-    const txHash = await blockchain.submitProofToVerifier(tradeId, proof);
+    // Submit the ABI-encoded proof exactly as export produced it
+    const txHash = await blockchain.submitPackedProofToVerifier(tradeId, proofData.packedProof);
 
     // Get transaction receipt to record the event
     const receipt = await blockchain.provider.getTransactionReceipt(txHash);
@@ -0,0 +1,87 @@
+#!/bin/bash
+
+# Full local round trip: trade on Anvil -> zkTLS proof against the fake Steam API -> submit
+# Usage: ./scripts/e2e-anvil.sh
+#
+# Expects a fresh Anvil from ./scripts/start-anvil.sh. Contracts are deployed with the
+# embedded notary's address as the trusted notary, and the notary signs keccak256 prehashes
+# (secp256k1eth), so the proof verifies on-chain with ecrecover.
+
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+ZKTLS_DIR="$PROJECT_DIR/steam-zktls"
+BIN="$ZKTLS_DIR/target/release"
+WORK_DIR="$(mktemp -d)"
+RPC_URL="http://localhost:8545"
+
+# Anvil accounts: 0 = deployer + notary key, 1 = buyer, 2 = seller, 3 = submitter
+DEPLOYER_KEY="0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+BUYER_KEY="0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"
+SELLER_KEY="0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a"
+SUBMITTER_KEY="0x7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6"
+SELLER="0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC"
+APP_ID=730
+
+cleanup() {
+  [ -n "${FAKE_PID:-}" ] && kill "$FAKE_PID" 2>/dev/null || true
+  rm -rf "$WORK_DIR"
+}
+trap cleanup EXIT
+
+for cmd in cast forge jq; do
+  command -v "$cmd" >/dev/null 2>&1 || { echo "Error: $cmd not found." >&2; exit 1; }
+done
+
+echo "Deploying contracts with the embedded notary as trusted notary..."
+# secp256k1eth keeps the key's Ethereum address, which ecrecover returns for its signatures
+NOTARY_ADDRESS=$(cast wallet address "$DEPLOYER_KEY")
+NOTARY_ADDRESS="$NOTARY_ADDRESS" "$SCRIPT_DIR/deploy.sh" >/dev/null
+
+BROADCAST="$PROJECT_DIR/contract/broadcast/Deploy.s.sol/31337/run-latest.json"
+address_of() { jq -r ".transactions[] | select(.contractName == \"$1\") | .contractAddress" "$BROADCAST" | head -1; }
+USDC=$(address_of MockUSDC)
+ESCROW=$(address_of SteamGameEscrow)
+VERIFIER=$(address_of SteamGameVerifier)
+
+echo "Creating and acknowledging a trade..."
+cast send -q "$USDC" "approve(address,uint256)" "$ESCROW" 10000000 --private-key "$BUYER_KEY" --rpc-url "$RPC_URL"
+TRADE_ID=$(cast call "$ESCROW" "nextTradeId()(uint256)" --rpc-url "$RPC_URL")
+cast send -q "$ESCROW" "createTrade(uint256,address,string)" "$APP_ID" "$SELLER" ohnoitspanda --private-key "$BUYER_KEY" --rpc-url "$RPC_URL"
+cast send -q "$USDC" "approve(address,uint256)" "$ESCROW" 1000000 --private-key "$SELLER_KEY" --rpc-url "$RPC_URL"
+cast send -q "$ESCROW" "acknowledge(uint256)" "$TRADE_ID" --private-key "$SELLER_KEY" --rpc-url "$RPC_URL"
+
+echo "Building steam-zktls..."
+(cd "$ZKTLS_DIR" && cargo build --release)
+
+# The contract only accepts api.steampowered.com, so the fake server impersonates it
+"$BIN/fake-steam" --listen 127.0.0.1:8443 --host api.steampowered.com --ca-out "$WORK_DIR/ca.pem" &
+FAKE_PID=$!
+sleep 1
+
+echo "Proving ownership of $APP_ID..."
+STEAM_API_KEY=0123456789ABCDEF0123456789ABCDEF "$BIN/prover" -v ohnoitspanda -a "$APP_ID" -o "$WORK_DIR/proof" \
+  --server-addr 127.0.0.1:8443 --root-cert "$WORK_DIR/ca.pem" \
+  --embedded-notary --notary-key "$DEPLOYER_KEY" --notary-alg secp256k1eth
+"$BIN/present" -a "$APP_ID" -i "$WORK_DIR/proof" -o "$WORK_DIR/proof.presentation.tlsn"
+"$BIN/export" -a "$APP_ID" -i "$WORK_DIR/proof.presentation.tlsn" -o "$WORK_DIR/proof.json" --root-cert "$WORK_DIR/ca.pem"
+
+PROOF_NOTARY=$(jq -r .notaryAddress "$WORK_DIR/proof.json")
+if [ "$(echo "$PROOF_NOTARY" | tr 'A-F' 'a-f')" != "$(echo "$NOTARY_ADDRESS" | tr 'A-F' 'a-f')" ]; then
+  echo "FAIL: proof is signed by $PROOF_NOTARY, contracts trust $NOTARY_ADDRESS" >&2
+  exit 1
+fi
+
+echo "Submitting proof for trade $TRADE_ID..."
+OUTPUT=$("$BIN/submit" -p "$WORK_DIR/proof.json" -t "$TRADE_ID" --verifier "$VERIFIER" \
+  --rpc-url "$RPC_URL" --private-key "$SUBMITTER_KEY")
+echo "$OUTPUT"
+
+if ! echo "$OUTPUT" | grep -q "Buyer Owns Game:  true"; then
+  echo "FAIL: expected TradeResolved with buyerOwnsGame=true" >&2
+  exit 1
+fi
+
+echo ""
+echo "On-chain round trip passed."
@@ -0,0 +1,70 @@
+#!/bin/bash
+
+# Run prover -> present -> verifier -> export against the fake Steam API
+# Usage: ./scripts/e2e-local.sh
+#
+# Uses the prover's embedded notary, so no other services are needed.
+
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
+PROJECT_DIR="$(dirname "$SCRIPT_DIR")"
+ZKTLS_DIR="$PROJECT_DIR/steam-zktls"
+BIN="$ZKTLS_DIR/target/release"
+WORK_DIR="$(mktemp -d)"
+
+FAKE_HOST="localhost"
+FAKE_ADDR="127.0.0.1:8443"
+# Anvil account 0; fine for throwaway local attestations. export only takes keccak256-prehashed
+# (secp256k1eth) notaries for ecrecover.
+NOTARY_KEY="ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+
+cleanup() {
+  [ -n "${FAKE_PID:-}" ] && kill "$FAKE_PID" 2>/dev/null || true
+  rm -rf "$WORK_DIR"
+}
+trap cleanup EXIT
+
+echo "Building steam-zktls..."
+(cd "$ZKTLS_DIR" && cargo build --release)
+
+echo "Starting fake Steam API on $FAKE_ADDR..."
+"$BIN/fake-steam" --listen "$FAKE_ADDR" --host "$FAKE_HOST" --ca-out "$WORK_DIR/ca.pem" &
+FAKE_PID=$!
+sleep 1
+
+export STEAM_API_KEY=0123456789ABCDEF0123456789ABCDEF
+export STEAM_API_HOST="$FAKE_HOST"
+export STEAM_API_ADDR="$FAKE_ADDR"
+export STEAM_API_ROOT_CERT="$WORK_DIR/ca.pem"
+
+# prove <app_id> <expected ownsGame>
+prove() {
+  local app_id=$1 expected=$2
+  local prefix="$WORK_DIR/app_$app_id"
+
+  echo ""
+  echo "=== app_id $app_id (expect ownsGame=$expected) ==="
+  "$BIN/prover" -v ohnoitspanda -a "$app_id" -o "$prefix" \
+    --embedded-notary --notary-key "$NOTARY_KEY" --notary-alg secp256k1eth
+  "$BIN/present" -a "$app_id" -i "$prefix" -o "$prefix.presentation.tlsn"
+
+  local result
+  result=$("$BIN/verifier" -a "$app_id" -p "$prefix.presentation.tlsn" --json \
+    --expected-server "$FAKE_HOST" --root-cert "$WORK_DIR/ca.pem" || true)
+  echo "$result"
+
+  if ! echo "$result" | grep -q "\"ownsGame\": $expected"; then
+    echo "FAIL: expected ownsGame=$expected" >&2
+    exit 1
+  fi
+
+  "$BIN/export" -a "$app_id" -i "$prefix.presentation.tlsn" -o "$prefix.proof.json" \
+    --expected-server "$FAKE_HOST" --root-cert "$WORK_DIR/ca.pem"
+}
+
+prove 730 true
+prove 1245620 false
+
+echo ""
+echo "All local end-to-end checks passed."
@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Result};
+use tiny_keccak::{Hasher, Keccak};
+
+/// A single Solidity ABI value
+#[derive(Debug, Clone)]
+pub enum Token {
+    /// Any `uintN`, left-padded to 32 bytes
+    Uint([u8; 32]),
+    /// `bytes32`
+    FixedBytes([u8; 32]),
+    /// `address`
+    Address([u8; 20]),
+    /// `bool`
+    Bool(bool),
+    /// `string`
+    String(String),
+    /// `bytes`
+    Bytes(Vec<u8>),
+}
+
+impl Token {
+    /// `uintN` from a u64
+    pub fn uint(value: u64) -> Self {
+        let mut word = [0u8; 32];
+        word[24..].copy_from_slice(&value.to_be_bytes());
+        Token::Uint(word)
+    }
+
+    fn is_dynamic(&self) -> bool {
+        matches!(self, Token::String(_) | Token::Bytes(_))
+    }
+
+    /// Static value as a single word
+    fn head(&self) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        match self {
+            Token::Uint(w) | Token::FixedBytes(w) => word = *w,
+            Token::Address(a) => word[12..].copy_from_slice(a),
+            Token::Bool(b) => word[31] = *b as u8,
+            Token::String(_) | Token::Bytes(_) => unreachable!("dynamic tokens have no static head"),
+        }
+        word
+    }
+
+    /// Length-prefixed, zero-padded tail of a dynamic value
+    fn tail(&self) -> Vec<u8> {
+        let data = match self {
+            Token::String(s) => s.as_bytes(),
+            Token::Bytes(b) => b.as_slice(),
+            _ => unreachable!("static tokens have no tail"),
+        };
+
+        let mut out = Token::uint(data.len() as u64).head().to_vec();
+        out.extend_from_slice(data);
+        out.resize(out.len() + (32 - data.len() % 32) % 32, 0);
+        out
+    }
+}
+
+/// `abi.encode(tokens...)`
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let head_len = tokens.len() * 32;
+    let mut heads = Vec::with_capacity(head_len);
+    let mut tails = Vec::new();
+
+    for token in tokens {
+        if token.is_dynamic() {
+            // Offset of the tail from the start of this encoding
+            heads.extend_from_slice(&Token::uint((head_len + tails.len()) as u64).head());
+            tails.extend_from_slice(&token.tail());
+        } else {
+            heads.extend_from_slice(&token.head());
+        }
+    }
+
+    heads.extend_from_slice(&tails);
+    heads
+}
+
+/// Full calldata: 4-byte selector of `signature` followed by `abi.encode(tokens...)`
+pub fn encode_call(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut calldata = selector(signature).to_vec();
+    calldata.extend_from_slice(&encode(tokens));
+    calldata
+}
+
+/// First 4 bytes of keccak256 of a function or error signature
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// keccak256 as used by Solidity
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+/// Parse a 0x-prefixed (or bare) hex string into a `bytes32`
+pub fn parse_bytes32(s: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(s.trim_start_matches("0x"))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| anyhow!("Expected 32 bytes, got {}", b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hex of 32-byte words, for comparing encodings word by word
+    fn words(words: &[&str]) -> String {
+        words.iter().map(|w| format!("{:0>64}", w)).collect()
+    }
+
+    #[test]
+    fn selectors_match_solidity() {
+        assert_eq!(hex::encode(selector("transfer(address,uint256)")), "a9059cbb");
+        assert_eq!(hex::encode(selector("baz(uint32,bool)")), "cdcd77c0");
+    }
+
+    #[test]
+    fn keccak256_of_empty_input() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[test]
+    fn static_call_matches_the_abi_spec_example() {
+        // baz(69, true) from the Solidity ABI specification
+        let calldata = encode_call("baz(uint32,bool)", &[Token::uint(69), Token::Bool(true)]);
+
+        assert_eq!(hex::encode(calldata), format!("cdcd77c0{}", words(&["45", "1"])));
+    }
+
+    #[test]
+    fn dynamic_values_are_offset_into_padded_tails() {
+        let encoded = encode(&[
+            Token::String("dave".to_string()),
+            Token::Bool(true),
+            Token::Bytes(b"Hello, world!".to_vec()),
+        ]);
+
+        let expected = words(&[
+            "60",
+            "1",
+            "a0",
+            "4",
+            "6461766500000000000000000000000000000000000000000000000000000000",
+            "d",
+            "48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+        ]);
+        assert_eq!(hex::encode(encoded), expected);
+    }
+
+    #[test]
+    fn addresses_and_bytes32_fill_one_word() {
+        let encoded = encode(&[Token::Address([0x11; 20]), Token::FixedBytes([0x22; 32])]);
+
+        let expected = words(&[&"11".repeat(20), &"22".repeat(32)]);
+        assert_eq!(hex::encode(encoded), expected);
+    }
+
+    #[test]
+    fn bytes32_parses_with_or_without_prefix() {
+        let hex32 = "ab".repeat(32);
+
+        assert_eq!(parse_bytes32(&format!("0x{}", hex32)).unwrap(), [0xab; 32]);
+        assert_eq!(parse_bytes32(&hex32).unwrap(), [0xab; 32]);
+        assert!(parse_bytes32("0xabcd").is_err());
+    }
+}
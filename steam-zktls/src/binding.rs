@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tlsn_core::{
+    attestation::{Attestation, AttestationProof},
+    connection::{ConnectionInfo, ServerIdentityProof},
+    presentation::{Presentation, PresentationOutput},
+    signing::{Signature, SignatureAlgId},
+    transcript::TranscriptProof,
+    CryptoProvider,
+};
+
+use crate::{
+    ownership,
+    signature::{self, OnChainSignature},
+    types::{SignatureBinding, SolidityProof},
+};
+
+/// A presentation's fields in serialization order, to take its openings apart
+#[derive(Deserialize)]
+struct PresentationParts {
+    attestation: AttestationProof,
+    identity: Option<ServerIdentityProof>,
+    transcript: Option<TranscriptProof>,
+}
+
+/// Collect the signed header, body and openings behind an exported proof.
+///
+/// `presentation` is the BCS-serialized presentation the attestation was verified from. Only
+/// its openings are kept; the header, body and signature it repeats are carried once.
+pub fn build(
+    presentation: &[u8],
+    attestation: &Attestation,
+    connection_info: &ConnectionInfo,
+) -> Result<SignatureBinding> {
+    let parts: PresentationParts = bcs::from_bytes(presentation)?;
+    let header = bcs::to_bytes(&attestation.header)?;
+    let body = bcs::to_bytes(&attestation.body)?;
+
+    // The attestation proof is signature || header || body || Merkle proof of the body fields
+    let attestation_proof = bcs::to_bytes(&parts.attestation)?;
+    let signed = [bcs::to_bytes(&attestation.signature)?, header.clone(), body.clone()].concat();
+    let body_proof = attestation_proof
+        .strip_prefix(signed.as_slice())
+        .ok_or_else(|| anyhow!("Attestation proof does not start with the signed attestation"))?;
+
+    let identity = parts.identity.ok_or_else(|| anyhow!("Presentation has no server identity proof"))?;
+    let transcript = parts.transcript.ok_or_else(|| anyhow!("Presentation has no transcript proof"))?;
+
+    Ok(SignatureBinding {
+        header: format!("0x{}", hex::encode(header)),
+        body: format!("0x{}", hex::encode(body)),
+        connection_info: format!("0x{}", hex::encode(bcs::to_bytes(connection_info)?)),
+        signature_alg: attestation.signature.alg.to_string(),
+        signature: format!("0x{}", hex::encode(&attestation.signature.data)),
+        body_proof: format!("0x{}", hex::encode(body_proof)),
+        identity_proof: format!("0x{}", hex::encode(bcs::to_bytes(&identity)?)),
+        transcript_proof: format!("0x{}", hex::encode(bcs::to_bytes(&transcript)?)),
+    })
+}
+
+/// Reassemble the presentation from the signed data and openings in a binding
+fn presentation(binding: &SignatureBinding) -> Result<Presentation> {
+    let alg = [SignatureAlgId::SECP256K1, SignatureAlgId::SECP256K1ETH, SignatureAlgId::SECP256R1]
+        .into_iter()
+        .find(|alg| alg.to_string() == binding.signature_alg)
+        .ok_or_else(|| anyhow!("Unsupported signature algorithm {}", binding.signature_alg))?;
+    let signature = Signature {
+        alg,
+        data: decode_hex(&binding.signature)?,
+    };
+
+    let mut bytes = [
+        bcs::to_bytes(&signature)?,
+        decode_hex(&binding.header)?,
+        decode_hex(&binding.body)?,
+        decode_hex(&binding.body_proof)?,
+    ]
+    .concat();
+    // Both proofs are present (`Some`)
+    bytes.push(1);
+    bytes.extend(decode_hex(&binding.identity_proof)?);
+    bytes.push(1);
+    bytes.extend(decode_hex(&binding.transcript_proof)?);
+
+    Ok(bcs::from_bytes(&bytes)?)
+}
+
+/// Reference check: re-derive every `SolidityProof` field from data bound to the signature.
+///
+/// Mirrors what a contract checks with `ecrecover` or `P256VERIFY`, then closes the gap the
+/// contract leaves open by tying `ownsGame`, `timestamp`, `serverName` and `transcriptHash`
+/// to the signed header through the openings, and checks the request was for `app_id`.
+pub fn check(proof: &SolidityProof, provider: &CryptoProvider, expected_server: &str, app_id: u32) -> Result<()> {
+    let binding = &proof.binding;
+
+    // 1. The openings verify (including the notary signature) against exactly the exported
+    //    header, body and signature they are reassembled with
+    let PresentationOutput {
+        attestation,
+        server_name,
+        connection_info,
+        transcript,
+        ..
+    } = presentation(binding)?.verify(provider)?;
+
+    // 2. messageHash and the signature are what the contract needs to check the notary key
+    //    committed in the body: ecrecover for secp256k1eth, P256VERIFY for secp256r1
+    let signature = signature::on_chain_signature(&attestation)?;
+    expect_eq("messageHashAlg", &proof.message_hash_alg, &signature.prehash().to_string())?;
+    expect_eq("messageHash", &proof.message_hash, &hex32(signature.message_hash()))?;
+    expect_eq("signatureR", &proof.signature_r, &hex32(signature.r()))?;
+    expect_eq("signatureS", &proof.signature_s, &hex32(signature.s()))?;
+
+    match &signature {
+        OnChainSignature::Ecrecover(signature) => {
+            expect_eq("signatureV", &proof.signature_v, &Some(signature.v))?;
+            expect_eq(
+                "notaryAddress",
+                &proof.notary_address.as_ref().map(|a| a.to_lowercase()),
+                &Some(format!("0x{}", hex::encode(signature.address))),
+            )?;
+        }
+        OnChainSignature::P256(signature) => {
+            let p256 = proof.p256.as_ref().ok_or_else(|| anyhow!("secp256r1 proof has no p256 section"))?;
+            expect_eq("p256.qx", &p256.qx, &hex32(signature.qx))?;
+            expect_eq("p256.qy", &p256.qy, &hex32(signature.qy))?;
+            expect_eq(
+                "p256.precompileInput",
+                &p256.precompile_input,
+                &format!("0x{}", hex::encode(signature.precompile_input())),
+            )?;
+        }
+    }
+
+    // 3. Timestamp comes from the connection info under the header root
+    if bcs::to_bytes(&connection_info)? != decode_hex(&binding.connection_info)? {
+        return Err(anyhow!("Connection info does not match the opened body field"));
+    }
+    expect_eq("timestamp", &proof.timestamp, &connection_info.time)?;
+
+    // 4. Server name, ownership and transcript hash come from the opened transcript
+    let server_name = server_name.ok_or_else(|| anyhow!("No server name in openings"))?;
+    expect_eq("serverName", &proof.server_name.as_str(), &server_name.as_str())?;
+
+    let transcript = transcript.ok_or_else(|| anyhow!("No transcript in openings"))?;
+    let result = ownership::verification_result(
+        server_name.as_str(),
+        expected_server,
+        connection_info.time,
+        transcript,
+        Some(app_id),
+    )?;
+    expect_eq("ownsGame", &proof.owns_game, &result.owns_game)?;
+    expect_eq("transcriptHash", &proof.transcript_hash, &result.transcript_hash)?;
+
+    Ok(())
+}
+
+fn expect_eq<T: PartialEq + std::fmt::Debug>(field: &str, claimed: &T, derived: &T) -> Result<()> {
+    if claimed != derived {
+        return Err(anyhow!("{} mismatch: proof says {:?}, signed data says {:?}", field, claimed, derived));
+    }
+    Ok(())
+}
+
+fn hex32(bytes: [u8; 32]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    Ok(hex::decode(s.trim_start_matches("0x"))?)
+}
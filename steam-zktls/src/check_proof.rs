@@ -0,0 +1,72 @@
+mod abi;
+mod binding;
+mod config;
+mod eth;
+mod logging;
+mod signature;
+mod tls;
+
+use steam_zktls::{ownership, types};
+
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::Parser;
+use tracing::info;
+
+use tls::STEAM_API_HOST;
+use types::SolidityProof;
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Re-derive an exported proof's fields from the notary-signed data and compare"
+)]
+struct Args {
+    /// Proof JSON produced by `export`
+    #[arg(short, long, default_value = "steam_ownership.proof.json")]
+    proof: String,
+
+    /// App ID the proof must be for
+    #[arg(short, long)]
+    app_id: u32,
+
+    /// Server name the proof must have been generated against
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    expected_server: String,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+fn main() -> Result<ExitCode> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+async fn run() -> Result<ExitCode> {
+    let mut args = Args::parse();
+    args.proof = config::artifact_path(&args.proof);
+    logging::init(&args.log, "info");
+
+    info!("Checking proof {}", args.proof);
+
+    let proof: SolidityProof = serde_json::from_str(&tokio::fs::read_to_string(&args.proof).await?)?;
+    let provider = tls::crypto_provider(args.root_cert.as_deref())?;
+
+    match binding::check(&proof, &provider, &args.expected_server, args.app_id) {
+        Ok(()) => {
+            println!("OK: every field is bound to the notary signature");
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => {
+            println!("MISMATCH: {}", e);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
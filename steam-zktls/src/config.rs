@@ -0,0 +1,211 @@
+//! `steam-zktls.toml`: settings shared by every binary.
+//!
+//! Precedence is file < environment < flags. [`load`] exports each setting in the file as
+//! its environment variable unless that variable is already set (by the shell or `.env`),
+//! and clap reads flags over environment variables.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Config file read when `STEAM_ZKTLS_CONFIG` is unset (skipped if missing)
+const DEFAULT_CONFIG_PATH: &str = "steam-zktls.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    steam: SteamSection,
+    notary: NotarySection,
+    limits: LimitsSection,
+    disclosure: DisclosureSection,
+    freshness: FreshnessSection,
+    output: OutputSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SteamSection {
+    api_host: Option<String>,
+    api_addr: Option<String>,
+    root_cert: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NotarySection {
+    host: Option<String>,
+    port: Option<u16>,
+    /// `host:port` of each notary in failover order (instead of host and port)
+    endpoints: Vec<String>,
+    signing_key: Option<String>,
+    signature_alg: Option<String>,
+    /// Hex public keys whose presentations the verifiers accept (any if empty)
+    trusted_keys: Vec<String>,
+    /// JSON trust store of keys with validity windows
+    trust_store: Option<String>,
+    /// Distinct trusted notaries a multi-notary bundle needs
+    threshold: Option<usize>,
+    /// `host:port` of every notary a multi-notary run attests with
+    multi: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LimitsSection {
+    max_sent_data: Option<usize>,
+    max_recv_data: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DisclosureSection {
+    /// Request headers revealed besides the response
+    reveal_headers: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FreshnessSection {
+    /// Oldest acceptable TLS connection time, in seconds before now
+    max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OutputSection {
+    /// Directory relative artifact paths are resolved against
+    dir: Option<String>,
+}
+
+impl ConfigFile {
+    /// Every setting as (environment variable, value)
+    fn env_vars(self) -> Vec<(&'static str, String)> {
+        let list = |values: Vec<String>| (!values.is_empty()).then(|| values.join(","));
+
+        [
+            ("STEAM_API_HOST", self.steam.api_host),
+            ("STEAM_API_ADDR", self.steam.api_addr),
+            ("STEAM_API_ROOT_CERT", self.steam.root_cert),
+            ("NOTARY_HOST", self.notary.host),
+            ("NOTARY_PORT", self.notary.port.map(|p| p.to_string())),
+            ("NOTARY_ENDPOINTS", list(self.notary.endpoints)),
+            ("NOTARY_SIGNING_KEY", self.notary.signing_key),
+            ("NOTARY_SIGNATURE_ALG", self.notary.signature_alg),
+            ("TRUSTED_NOTARY_KEYS", list(self.notary.trusted_keys)),
+            ("NOTARY_TRUST_STORE", self.notary.trust_store),
+            ("NOTARY_THRESHOLD", self.notary.threshold.map(|n| n.to_string())),
+            ("MULTI_NOTARY", list(self.notary.multi)),
+            ("MAX_SENT_DATA", self.limits.max_sent_data.map(|n| n.to_string())),
+            ("MAX_RECV_DATA", self.limits.max_recv_data.map(|n| n.to_string())),
+            ("REVEAL_HEADERS", list(self.disclosure.reveal_headers)),
+            ("PROOF_MAX_AGE", self.freshness.max_age_secs.map(|n| n.to_string())),
+            ("OUTPUT_DIR", self.output.dir),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+/// Load `.env` and the config file into the environment. Call before parsing arguments, and
+/// before starting the tokio runtime: setting environment variables while other threads may
+/// read them is a data race.
+pub fn load() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    let (path, required) = match std::env::var("STEAM_ZKTLS_CONFIG") {
+        Ok(path) => (path, true),
+        Err(_) => (DEFAULT_CONFIG_PATH.to_string(), false),
+    };
+    if !required && !Path::new(&path).exists() {
+        return Ok(());
+    }
+
+    let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read config {}", path))?;
+    let file: ConfigFile = toml::from_str(&text).with_context(|| format!("Invalid config {}", path))?;
+
+    for (name, value) in unset_vars(file, |name| std::env::var_os(name).is_some()) {
+        std::env::set_var(name, value);
+    }
+
+    Ok(())
+}
+
+/// Settings in `file` whose environment variable is not already set
+fn unset_vars(file: ConfigFile, is_set: impl Fn(&str) -> bool) -> Vec<(&'static str, String)> {
+    file.env_vars().into_iter().filter(|(name, _)| !is_set(name)).collect()
+}
+
+/// Resolve a relative artifact path against `OUTPUT_DIR`, if set. `-` (stdin) is left alone.
+pub fn artifact_path(path: &str) -> String {
+    match std::env::var("OUTPUT_DIR") {
+        Ok(dir) if path != "-" && Path::new(path).is_relative() => {
+            Path::new(&dir).join(path).to_string_lossy().into_owned()
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [steam]
+        api_host = "localhost"
+
+        [notary]
+        host = "notary.internal"
+        endpoints = ["a:7047", "b:7047"]
+        threshold = 2
+    "#;
+
+    #[test]
+    fn file_settings_map_to_environment_variables() {
+        let file: ConfigFile = toml::from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            file.env_vars(),
+            [
+                ("STEAM_API_HOST", "localhost".to_string()),
+                ("NOTARY_HOST", "notary.internal".to_string()),
+                ("NOTARY_ENDPOINTS", "a:7047,b:7047".to_string()),
+                ("NOTARY_THRESHOLD", "2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let file: ConfigFile = toml::from_str(CONFIG).unwrap();
+
+        let vars = unset_vars(file, |name| name == "NOTARY_HOST");
+
+        assert!(vars.iter().all(|(name, _)| *name != "NOTARY_HOST"));
+        assert!(vars.contains(&("STEAM_API_HOST", "localhost".to_string())));
+    }
+
+    #[test]
+    fn flags_override_the_environment() {
+        #[derive(Parser)]
+        struct Flags {
+            #[arg(long, env = "STEAM_ZKTLS_CONFIG_TEST_HOST")]
+            host: String,
+        }
+
+        // Only this test reads or writes the variable
+        std::env::set_var("STEAM_ZKTLS_CONFIG_TEST_HOST", "from-env");
+
+        assert_eq!(Flags::parse_from(["test"]).host, "from-env");
+        assert_eq!(Flags::parse_from(["test", "--host", "from-flag"]).host, "from-flag");
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        assert!(toml::from_str::<ConfigFile>("[notary]\nhots = \"typo\"").is_err());
+        assert!(toml::from_str::<ConfigFile>("[stema]").is_err());
+    }
+}
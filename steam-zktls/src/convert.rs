@@ -0,0 +1,99 @@
+mod config;
+mod logging;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use steam_zktls::envelope::{self, ArtifactKind, TLSN_VERSION};
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Wrap legacy raw .tlsn files in the versioned envelope, or show an envelope's header"
+)]
+struct Args {
+    /// Files to convert in place
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Artifact kind (guessed from `.attestation.tlsn` / `.secrets.tlsn` / `.presentation.tlsn` /
+    /// `.bundle.tlsn` if unset)
+    #[arg(short, long, value_enum)]
+    kind: Option<ArtifactKind>,
+
+    /// Only print each file's envelope header
+    #[arg(long, default_value = "false")]
+    inspect: bool,
+
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, "info");
+
+    for path in &args.files {
+        let bytes = tokio::fs::read(path).await?;
+
+        if args.inspect {
+            match envelope::open(&bytes)? {
+                Some((header, payload)) => println!(
+                    "{}: {} ({}), tlsn {}, created {}, {} byte payload",
+                    path,
+                    header.kind,
+                    header.claim_type,
+                    header.tlsn_version,
+                    header.created_at,
+                    payload.len()
+                ),
+                None => println!("{}: no envelope (legacy raw file)", path),
+            }
+            continue;
+        }
+
+        let kind = args
+            .kind
+            .or_else(|| ArtifactKind::from_path(path))
+            .ok_or_else(|| anyhow!("{}: cannot tell the artifact kind from the name, pass --kind", path))?;
+
+        let converted = envelope::convert(kind, &bytes).map_err(|e| anyhow!("{}: {}", path, e))?;
+        replace(path, &converted).await?;
+
+        info!("{}: wrapped as {} (tlsn {})", path, kind, TLSN_VERSION);
+    }
+
+    Ok(())
+}
+
+/// Replace `path` with `contents` atomically: write a temporary file next to it, then rename it
+/// over the original, so an interrupted run leaves either the old or the new file
+async fn replace(path: &str, contents: &[u8]) -> Result<()> {
+    let tmp = format!("{}.{}.tmp", path, std::process::id());
+
+    if let Err(e) = write_synced(&tmp, contents).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(anyhow!("{}: cannot write {}: {}", path, tmp, e));
+    }
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(anyhow!("{}: cannot replace with {}: {}", path, tmp, e));
+    }
+
+    Ok(())
+}
+
+/// Write `contents` to a new file and flush it to disk
+async fn write_synced(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = tokio::fs::File::create(path).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await
+}
@@ -0,0 +1,112 @@
+//! What an ownership proof reveals of the transcript. The prover commits to exactly these
+//! ranges and `present` reveals them, so the two cannot drift apart.
+
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use crate::ownership::HIDDEN_PARAMS;
+
+/// Byte ranges of the transcript to reveal
+#[derive(Debug, Clone)]
+pub struct Disclosure {
+    /// Ranges of the request to reveal
+    pub sent: Vec<Range<usize>>,
+    /// Ranges of the response to reveal
+    pub recv: Vec<Range<usize>>,
+}
+
+/// Reveal the request line except the low digits of the percent-encoded API key and Steam ID
+/// (so the verifier sees the free-game settings and the hidden values' charset), the Host
+/// header (proves it's from Steam), any other request headers in `headers` that are present,
+/// and the whole filtered response
+pub fn ownership_disclosure(sent: &[u8], recv: &[u8], headers: &[String]) -> Result<Disclosure> {
+    let host = find_header(sent, "host").ok_or_else(|| anyhow!("No Host header in request"))?;
+
+    let mut revealed = request_line(sent)?;
+    revealed.push(host);
+    for name in headers.iter().filter(|name| !name.eq_ignore_ascii_case("host")) {
+        if let Some(range) = find_header(sent, name) {
+            revealed.push(range);
+        }
+    }
+    revealed.sort_by_key(|range| range.start);
+
+    Ok(Disclosure {
+        sent: revealed,
+        recv: vec![0..recv.len()],
+    })
+}
+
+/// Ranges of the request line around the low digit of each `%XY` escape in the values of
+/// [`HIDDEN_PARAMS`]
+fn request_line(sent: &[u8]) -> Result<Vec<Range<usize>>> {
+    let end = sent
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .ok_or_else(|| anyhow!("No request line in request"))?;
+    let line = &sent[..end];
+
+    let query_start = line
+        .iter()
+        .position(|&b| b == b'?')
+        .ok_or_else(|| anyhow!("No query in request line"))?
+        + 1;
+    let query_end = query_start
+        + line[query_start..]
+            .iter()
+            .position(|&b| b == b' ')
+            .ok_or_else(|| anyhow!("Malformed request line"))?;
+
+    let mut ranges = Vec::new();
+    let mut revealed_from = 0;
+    let mut param_start = query_start;
+    for param in line[query_start..query_end].split(|&b| b == b'&') {
+        if let Some(eq) = param.iter().position(|&b| b == b'=') {
+            if HIDDEN_PARAMS.iter().any(|hidden| hidden.name.as_bytes() == &param[..eq]) {
+                let value = &param[eq + 1..];
+                if value.len() % 3 != 0 || value.chunks_exact(3).any(|escape| escape[0] != b'%') {
+                    return Err(anyhow!(
+                        "Value of {} is not percent-encoded",
+                        String::from_utf8_lossy(&param[..eq])
+                    ));
+                }
+
+                let value_start = param_start + eq + 1;
+                for low_digit in (value_start + 2..value_start + value.len()).step_by(3) {
+                    ranges.push(revealed_from..low_digit);
+                    revealed_from = low_digit + 1;
+                }
+            }
+        }
+        param_start += param.len() + 1;
+    }
+    ranges.push(revealed_from..end);
+
+    Ok(ranges)
+}
+
+/// Range of a `Name: value` header line (without the trailing CRLF)
+fn find_header(data: &[u8], name: &str) -> Option<Range<usize>> {
+    let mut start = data.windows(2).position(|w| w == b"\r\n")? + 2;
+
+    while start < data.len() {
+        let end = start + data[start..].windows(2).position(|w| w == b"\r\n")?;
+        let line = &data[start..end];
+
+        // Blank line ends the headers
+        if line.is_empty() {
+            return None;
+        }
+
+        if let Some(colon) = line.iter().position(|&b| b == b':') {
+            if line[..colon].eq_ignore_ascii_case(name.as_bytes()) {
+                return Some(start..end);
+            }
+        }
+
+        start = end + 2;
+    }
+
+    None
+}
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::envelope::{self, ArtifactKind, MAGIC};
+
+/// How an artifact is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// The binary envelope as-is
+    Binary,
+    /// JSON wrapper with base64 data
+    Base64,
+    /// JSON wrapper with 0x-prefixed hex data
+    Hex,
+}
+
+/// Text-safe wrapper around an enveloped artifact, for JSON APIs
+#[derive(Debug, Serialize, Deserialize)]
+struct TextArtifact {
+    kind: ArtifactKind,
+    encoding: Encoding,
+    data: String,
+}
+
+/// Encode an enveloped artifact for writing
+pub fn encode(kind: ArtifactKind, bytes: Vec<u8>, encoding: Encoding) -> Result<Vec<u8>> {
+    let data = match encoding {
+        Encoding::Binary => return Ok(bytes),
+        Encoding::Base64 => BASE64.encode(&bytes),
+        Encoding::Hex => format!("0x{}", hex::encode(&bytes)),
+    };
+    Ok(serde_json::to_vec_pretty(&TextArtifact { kind, encoding, data })?)
+}
+
+/// Recover the binary artifact from any supported form: binary envelope, legacy raw bincode,
+/// a JSON wrapper, or a bare base64 or hex string
+pub fn decode(kind: ArtifactKind, input: &[u8]) -> Result<Vec<u8>> {
+    if input.starts_with(MAGIC) {
+        return Ok(input.to_vec());
+    }
+
+    // Anything that is not text is taken to be a legacy raw file
+    let Ok(text) = std::str::from_utf8(input).map(str::trim) else {
+        return Ok(input.to_vec());
+    };
+
+    if text.starts_with('{') {
+        let wrapper: TextArtifact =
+            serde_json::from_str(text).map_err(|e| anyhow!("Invalid {} JSON wrapper: {}", kind, e))?;
+        if wrapper.kind != kind {
+            return Err(anyhow!("Expected a {} wrapper, got a {} wrapper", kind, wrapper.kind));
+        }
+        return decode_text(&wrapper.data, wrapper.encoding);
+    }
+
+    let bare = text.trim_start_matches("0x");
+    if !bare.is_empty() && bare.len() % 2 == 0 && bare.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return decode_text(text, Encoding::Hex);
+    }
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_alphanumeric() || b"+/=\r\n".contains(&b)) {
+        return decode_text(text, Encoding::Base64);
+    }
+
+    Ok(input.to_vec())
+}
+
+fn decode_text(data: &str, encoding: Encoding) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Binary => Err(anyhow!("A JSON wrapper cannot carry binary data")),
+        Encoding::Base64 => {
+            let data: String = data.split_whitespace().collect();
+            Ok(BASE64.decode(data).map_err(|e| anyhow!("Invalid base64: {}", e))?)
+        }
+        Encoding::Hex => {
+            Ok(hex::decode(data.trim().trim_start_matches("0x")).map_err(|e| anyhow!("Invalid hex: {}", e))?)
+        }
+    }
+}
+
+/// Read a file, or stdin for `-`
+pub async fn read_input(path: &str) -> Result<Vec<u8>> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        tokio::io::stdin().read_to_end(&mut bytes).await?;
+        return Ok(bytes);
+    }
+
+    tokio::fs::read(path)
+        .await
+        .map_err(|e| anyhow!("Failed to read {}: {}", path, e))
+}
+
+/// Read an artifact in any supported encoding from a file or stdin (`-`)
+pub async fn read<T: DeserializeOwned>(kind: ArtifactKind, path: &str) -> Result<T> {
+    let bytes = decode(kind, &read_input(path).await?).map_err(|e| anyhow!("{}: {}", path, e))?;
+    envelope::unwrap(kind, &bytes).map_err(|e| anyhow!("{}: {}", path, e))
+}
+
+/// Write an artifact as an envelope in the given encoding (`Encoding::Binary` for the plain file)
+pub async fn write<T: Serialize>(kind: ArtifactKind, path: &str, value: &T, encoding: Encoding) -> Result<()> {
+    tokio::fs::write(path, encode(kind, envelope::wrap(kind, value)?, encoding)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact() -> Vec<u8> {
+        envelope::wrap(ArtifactKind::Presentation, &vec![1u32, 2, 3]).unwrap()
+    }
+
+    #[test]
+    fn every_encoding_round_trips() {
+        for encoding in [Encoding::Binary, Encoding::Base64, Encoding::Hex] {
+            let encoded = encode(ArtifactKind::Presentation, artifact(), encoding).unwrap();
+
+            assert_eq!(decode(ArtifactKind::Presentation, &encoded).unwrap(), artifact(), "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn bare_strings_are_decoded() {
+        let hex = format!("0x{}\n", hex::encode(artifact()));
+        let base64 = BASE64.encode(artifact());
+
+        assert_eq!(decode(ArtifactKind::Presentation, hex.as_bytes()).unwrap(), artifact());
+        assert_eq!(decode(ArtifactKind::Presentation, base64.as_bytes()).unwrap(), artifact());
+    }
+
+    #[test]
+    fn legacy_raw_bytes_pass_through() {
+        let raw = vec![0xff, 0x00, 0x9f];
+
+        assert_eq!(decode(ArtifactKind::Presentation, &raw).unwrap(), raw);
+    }
+
+    #[test]
+    fn wrapper_of_another_kind_is_rejected() {
+        let encoded = encode(ArtifactKind::Attestation, artifact(), Encoding::Base64).unwrap();
+
+        assert!(decode(ArtifactKind::Presentation, &encoded).is_err());
+    }
+
+    #[test]
+    fn malformed_wrappers_are_rejected() {
+        let bad_hex = br#"{"kind":"presentation","encoding":"hex","data":"0xzz"}"#;
+        let binary = br#"{"kind":"presentation","encoding":"binary","data":""}"#;
+
+        assert!(decode(ArtifactKind::Presentation, bad_hex).is_err());
+        assert!(decode(ArtifactKind::Presentation, binary).is_err());
+        assert!(decode(ArtifactKind::Presentation, b"{ not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn written_files_read_back() {
+        let path = std::env::temp_dir().join(format!("steam-zktls-encoding-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        write(ArtifactKind::Presentation, path, &vec![1u32, 2, 3], Encoding::Hex).await.unwrap();
+        let read_back: Vec<u32> = read(ArtifactKind::Presentation, path).await.unwrap();
+        tokio::fs::remove_file(path).await.unwrap();
+
+        assert_eq!(read_back, [1, 2, 3]);
+    }
+}
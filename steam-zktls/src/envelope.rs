@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// First bytes of every enveloped artifact
+pub const MAGIC: &[u8; 4] = b"SZKT";
+/// Current envelope layout version
+pub const FORMAT_VERSION: u16 = 1;
+/// tlsn release the payloads are serialized with
+pub const TLSN_VERSION: &str = "0.1.0-alpha.7";
+/// Claim type for Steam ownership artifacts
+pub const CLAIM_TYPE: &str = "steam-ownership";
+
+/// What an envelope carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    Attestation,
+    Secrets,
+    Presentation,
+    /// Presentations of the same claim from several notaries
+    Bundle,
+}
+
+impl ArtifactKind {
+    /// Guess the kind from the `<prefix>.<kind>.tlsn` naming the binaries use
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path.ends_with(".attestation.tlsn") {
+            Some(ArtifactKind::Attestation)
+        } else if path.ends_with(".secrets.tlsn") {
+            Some(ArtifactKind::Secrets)
+        } else if path.ends_with(".presentation.tlsn") {
+            Some(ArtifactKind::Presentation)
+        } else if path.ends_with(".bundle.tlsn") {
+            Some(ArtifactKind::Bundle)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactKind::Attestation => write!(f, "attestation"),
+            ArtifactKind::Secrets => write!(f, "secrets"),
+            ArtifactKind::Presentation => write!(f, "presentation"),
+            ArtifactKind::Bundle => write!(f, "bundle"),
+        }
+    }
+}
+
+/// Metadata written in front of the payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    pub tlsn_version: String,
+    pub kind: ArtifactKind,
+    pub claim_type: String,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+/// Wrap an artifact: `MAGIC | format version (u16 BE) | header length (u32 BE) | header | payload`,
+/// with header and payload in bincode
+pub fn wrap<T: Serialize>(kind: ArtifactKind, value: &T) -> Result<Vec<u8>> {
+    wrap_payload(kind, bincode::serialize(value)?)
+}
+
+fn wrap_payload(kind: ArtifactKind, payload: Vec<u8>) -> Result<Vec<u8>> {
+    let header = bincode::serialize(&EnvelopeHeader {
+        tlsn_version: TLSN_VERSION.to_string(),
+        kind,
+        claim_type: CLAIM_TYPE.to_string(),
+        created_at: chrono::Utc::now().timestamp() as u64,
+    })?;
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    bytes.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Split an envelope into its header and payload; `None` for files without one
+pub fn open(bytes: &[u8]) -> Result<Option<(EnvelopeHeader, &[u8])>> {
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        return Ok(None);
+    };
+
+    let truncated = || anyhow!("Truncated envelope");
+    let format_version = u16::from_be_bytes(rest.get(..2).ok_or_else(truncated)?.try_into()?);
+    if format_version > FORMAT_VERSION {
+        return Err(anyhow!(
+            "Envelope format v{} is newer than this build supports (v{}); upgrade steam-zktls",
+            format_version,
+            FORMAT_VERSION
+        ));
+    }
+
+    let header_len = u32::from_be_bytes(rest.get(2..6).ok_or_else(truncated)?.try_into()?) as usize;
+    let header_bytes = rest.get(6..6 + header_len).ok_or_else(truncated)?;
+    let header: EnvelopeHeader = bincode::deserialize(header_bytes)?;
+
+    Ok(Some((header, &rest[6 + header_len..])))
+}
+
+/// Read an artifact of `kind`, checking its envelope; legacy raw files are accepted if they decode
+pub fn unwrap<T: DeserializeOwned>(kind: ArtifactKind, bytes: &[u8]) -> Result<T> {
+    let Some((header, payload)) = open(bytes)? else {
+        return bincode::deserialize(bytes).map_err(|e| {
+            anyhow!(
+                "Not a steam-zktls {} file: no envelope, and not a raw tlsn {} {} ({}). \
+                 It was probably written by a different tlsn version; regenerate it",
+                kind,
+                TLSN_VERSION,
+                kind,
+                e
+            )
+        });
+    };
+
+    if header.kind != kind {
+        return Err(anyhow!("Expected a {} file, got a {} file", kind, header.kind));
+    }
+    if header.claim_type != CLAIM_TYPE {
+        return Err(anyhow!("Expected a {} claim, got {}", CLAIM_TYPE, header.claim_type));
+    }
+    if header.tlsn_version != TLSN_VERSION {
+        return Err(anyhow!(
+            "{} was written with tlsn {}, this build uses tlsn {}; \
+             tlsn artifacts are not portable across versions, regenerate it with a matching prover",
+            kind,
+            header.tlsn_version,
+            TLSN_VERSION
+        ));
+    }
+
+    Ok(bincode::deserialize(payload)?)
+}
+
+/// Re-envelope a legacy raw file (or an older envelope) with the current header.
+///
+/// The payload is kept byte-for-byte; only tlsn-compatible payloads can be converted.
+pub fn convert(kind: ArtifactKind, bytes: &[u8]) -> Result<Vec<u8>> {
+    let payload = match open(bytes)? {
+        Some((header, _)) if header.tlsn_version != TLSN_VERSION => {
+            return Err(anyhow!(
+                "Written with tlsn {}, payloads cannot be converted to tlsn {}; regenerate it",
+                header.tlsn_version,
+                TLSN_VERSION
+            ));
+        }
+        Some((header, _)) if header.kind != kind => {
+            return Err(anyhow!("Expected a {} file, got a {} file", kind, header.kind));
+        }
+        Some((_, payload)) => payload,
+        None => bytes,
+    };
+
+    // Make sure the payload really is a tlsn artifact of this kind before stamping it
+    match kind {
+        ArtifactKind::Attestation => drop(unwrap::<tlsn_core::attestation::Attestation>(kind, payload)?),
+        ArtifactKind::Secrets => drop(unwrap::<tlsn_core::Secrets>(kind, payload)?),
+        ArtifactKind::Presentation => drop(unwrap::<tlsn_core::presentation::Presentation>(kind, payload)?),
+        ArtifactKind::Bundle => drop(unwrap::<Vec<tlsn_core::presentation::Presentation>>(kind, payload)?),
+    }
+
+    wrap_payload(kind, payload.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim() -> Vec<String> {
+        vec!["steam".to_string(), "ownership".to_string()]
+    }
+
+    /// An envelope around `claim()` with `header` in place of the one `wrap` writes
+    fn envelope_with(header: &EnvelopeHeader) -> Vec<u8> {
+        let header = bincode::serialize(header).unwrap();
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&bincode::serialize(&claim()).unwrap());
+        bytes
+    }
+
+    fn header() -> EnvelopeHeader {
+        EnvelopeHeader {
+            tlsn_version: TLSN_VERSION.to_string(),
+            kind: ArtifactKind::Presentation,
+            claim_type: CLAIM_TYPE.to_string(),
+            created_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn wrapped_artifacts_round_trip() {
+        let bytes = wrap(ArtifactKind::Presentation, &claim()).unwrap();
+
+        let (header, _) = open(&bytes).unwrap().unwrap();
+        assert_eq!(header.kind, ArtifactKind::Presentation);
+        assert_eq!(header.tlsn_version, TLSN_VERSION);
+        assert_eq!(unwrap::<Vec<String>>(ArtifactKind::Presentation, &bytes).unwrap(), claim());
+    }
+
+    #[test]
+    fn legacy_raw_files_are_accepted() {
+        let raw = bincode::serialize(&claim()).unwrap();
+
+        assert!(open(&raw).unwrap().is_none());
+        assert_eq!(unwrap::<Vec<String>>(ArtifactKind::Presentation, &raw).unwrap(), claim());
+    }
+
+    #[test]
+    fn wrong_kind_is_rejected() {
+        let bytes = wrap(ArtifactKind::Attestation, &claim()).unwrap();
+
+        let error = unwrap::<Vec<String>>(ArtifactKind::Presentation, &bytes).unwrap_err();
+        assert!(error.to_string().contains("Expected a presentation file"), "{}", error);
+    }
+
+    #[test]
+    fn tampered_headers_are_rejected() {
+        let other_claim = envelope_with(&EnvelopeHeader {
+            claim_type: "other".to_string(),
+            ..header()
+        });
+        let other_tlsn = envelope_with(&EnvelopeHeader {
+            tlsn_version: "0.1.0-alpha.6".to_string(),
+            ..header()
+        });
+
+        assert!(unwrap::<Vec<String>>(ArtifactKind::Presentation, &envelope_with(&header())).is_ok());
+        assert!(unwrap::<Vec<String>>(ArtifactKind::Presentation, &other_claim).is_err());
+        assert!(unwrap::<Vec<String>>(ArtifactKind::Presentation, &other_tlsn).is_err());
+        assert!(convert(ArtifactKind::Presentation, &other_tlsn).is_err());
+    }
+
+    #[test]
+    fn newer_formats_are_rejected() {
+        let mut bytes = wrap(ArtifactKind::Presentation, &claim()).unwrap();
+        bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+
+        let error = open(&bytes).unwrap_err();
+        assert!(error.to_string().contains("newer than this build supports"), "{}", error);
+    }
+
+    #[test]
+    fn truncated_envelopes_are_rejected() {
+        let bytes = wrap(ArtifactKind::Presentation, &claim()).unwrap();
+
+        assert!(open(&bytes[..5]).is_err());
+        assert!(open(&bytes[..12]).is_err());
+    }
+
+    #[test]
+    fn kind_follows_the_file_name() {
+        assert_eq!(ArtifactKind::from_path("out/a.attestation.tlsn"), Some(ArtifactKind::Attestation));
+        assert_eq!(ArtifactKind::from_path("a.secrets.tlsn"), Some(ArtifactKind::Secrets));
+        assert_eq!(ArtifactKind::from_path("a.presentation.tlsn"), Some(ArtifactKind::Presentation));
+        assert_eq!(ArtifactKind::from_path("a.bundle.tlsn"), Some(ArtifactKind::Bundle));
+        assert_eq!(ArtifactKind::from_path("a.tlsn"), None);
+    }
+}
@@ -0,0 +1,402 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use serde_json::{json, Value};
+
+use crate::abi::{keccak256, selector};
+
+/// Custom errors the verifier contracts can revert with
+const KNOWN_ERRORS: &[&str] = &[
+    "InvalidNotarySignature()",
+    "InvalidServerName()",
+    "InvalidTranscript()",
+    "ZeroAddress()",
+    "ProofBeforeAcknowledge(uint64,uint256)",
+    "ProofAfterWindow(uint64,uint256)",
+    "ProofInFuture(uint64,uint256)",
+];
+
+/// Ethereum address of a secp256k1 public key
+pub fn address(key: &VerifyingKey) -> [u8; 20] {
+    // Ethereum address = last 20 bytes of keccak256(pubkey[1..65]), skipping the 0x04 prefix
+    let uncompressed = key.to_encoded_point(false);
+    let hash = keccak256(&uncompressed.as_bytes()[1..]);
+
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// Parse a 0x-prefixed address
+pub fn parse_address(s: &str) -> Result<[u8; 20]> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).with_context(|| format!("Invalid address {}", s))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Address must be 20 bytes: {}", s))
+}
+
+/// Signing key from a hex private key
+pub fn parse_private_key(s: &str) -> Result<SigningKey> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x")).context("Private key must be hex")?;
+    Ok(SigningKey::from_slice(&bytes)?)
+}
+
+/// Signing key from an encrypted JSON keystore (geth/foundry format)
+pub fn load_keystore(path: &str, password: &str) -> Result<SigningKey> {
+    let bytes = eth_keystore::decrypt_key(path, password)
+        .map_err(|e| anyhow!("Failed to decrypt keystore {}: {}", path, e))?;
+    Ok(SigningKey::from_slice(&bytes)?)
+}
+
+/// Error object returned by a JSON-RPC node
+#[derive(Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    /// Revert data, if the node returned any
+    pub data: Option<Vec<u8>>,
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.data {
+            Some(data) => write!(f, "{} ({})", self.message, decode_revert(data)),
+            None => write!(f, "{} (code {})", self.message, self.code),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Minimal Ethereum JSON-RPC client
+pub struct RpcClient {
+    client: reqwest::Client,
+    url: String,
+    next_id: AtomicU64,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Call `method`; node errors come back as a downcastable [`RpcError`]
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or("unknown error").to_string(),
+                data: error["data"]
+                    .as_str()
+                    .and_then(|d| hex::decode(d.trim_start_matches("0x")).ok()),
+            }
+            .into());
+        }
+
+        Ok(response["result"].clone())
+    }
+}
+
+/// Parse a hex quantity (`"0x1a"`)
+pub fn quantity(value: &Value) -> Result<u128> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| anyhow!("Expected hex quantity, got {}", value))?;
+    Ok(u128::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
+
+/// How the transaction pays for gas
+#[derive(Debug, Clone, Copy)]
+pub enum GasPrice {
+    /// EIP-1559 (type 2) fees
+    Eip1559 { max_fee: u128, max_priority_fee: u128 },
+    /// Pre-London chains (EIP-155 legacy transaction)
+    Legacy { gas_price: u128 },
+}
+
+/// A contract call ready to be signed
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    pub gas_price: GasPrice,
+    pub to: [u8; 20],
+    pub data: Vec<u8>,
+}
+
+impl Transaction {
+    /// Sign and return the raw transaction for `eth_sendRawTransaction`
+    pub fn sign(&self, key: &SigningKey) -> Result<Vec<u8>> {
+        let mut fields = match self.gas_price {
+            GasPrice::Eip1559 { max_fee, max_priority_fee } => vec![
+                rlp_uint(self.chain_id as u128),
+                rlp_uint(self.nonce as u128),
+                rlp_uint(max_priority_fee),
+                rlp_uint(max_fee),
+                rlp_uint(self.gas_limit as u128),
+                rlp_bytes(&self.to),
+                rlp_uint(0),
+                rlp_bytes(&self.data),
+                rlp_list(&[]), // access list
+            ],
+            GasPrice::Legacy { gas_price } => vec![
+                rlp_uint(self.nonce as u128),
+                rlp_uint(gas_price),
+                rlp_uint(self.gas_limit as u128),
+                rlp_bytes(&self.to),
+                rlp_uint(0),
+                rlp_bytes(&self.data),
+            ],
+        };
+
+        let signing_payload = match self.gas_price {
+            GasPrice::Eip1559 { .. } => [vec![0x02], rlp_list(&fields)].concat(),
+            GasPrice::Legacy { .. } => {
+                // EIP-155 replay protection
+                let mut unsigned = fields.clone();
+                unsigned.extend([rlp_uint(self.chain_id as u128), rlp_uint(0), rlp_uint(0)]);
+                rlp_list(&unsigned)
+            }
+        };
+
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak256(&signing_payload))?;
+        let (r, s) = signature.split_bytes();
+
+        let v = match self.gas_price {
+            GasPrice::Eip1559 { .. } => recovery_id.to_byte() as u128,
+            GasPrice::Legacy { .. } => recovery_id.to_byte() as u128 + self.chain_id as u128 * 2 + 35,
+        };
+        fields.extend([rlp_uint(v), rlp_bytes(trim_zeros(&r)), rlp_bytes(trim_zeros(&s))]);
+
+        Ok(match self.gas_price {
+            GasPrice::Eip1559 { .. } => [vec![0x02], rlp_list(&fields)].concat(),
+            GasPrice::Legacy { .. } => rlp_list(&fields),
+        })
+    }
+}
+
+/// Human-readable revert reason for known custom errors, `Error(string)` and `Panic(uint256)`
+pub fn decode_revert(data: &[u8]) -> String {
+    if data.len() < 4 {
+        return format!("revert 0x{}", hex::encode(data));
+    }
+
+    let (sel, args) = data.split_at(4);
+    let words: Vec<&[u8]> = args.chunks(32).collect();
+
+    if sel == selector("Error(string)") && words.len() >= 2 {
+        let len = word_to_u128(words[1]) as usize;
+        let text = args.get(64..64 + len).map(String::from_utf8_lossy).unwrap_or_default();
+        return format!("Error(\"{}\")", text);
+    }
+
+    if sel == selector("Panic(uint256)") && !words.is_empty() {
+        return format!("Panic(0x{:x})", word_to_u128(words[0]));
+    }
+
+    for signature in KNOWN_ERRORS {
+        if sel == selector(signature) {
+            // All known error arguments are unsigned integers
+            let name = &signature[..signature.find('(').unwrap_or(signature.len())];
+            let values: Vec<String> = words.iter().map(|w| word_to_u128(w).to_string()).collect();
+            return format!("{}({})", name, values.join(", "));
+        }
+    }
+
+    format!("unknown revert 0x{}", hex::encode(data))
+}
+
+fn word_to_u128(word: &[u8]) -> u128 {
+    word.iter()
+        .skip(word.len().saturating_sub(16))
+        .fold(0u128, |acc, b| (acc << 8) | *b as u128)
+}
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn rlp_uint(value: u128) -> Vec<u8> {
+    rlp_bytes(trim_zeros(&value.to_be_bytes()))
+}
+
+fn rlp_bytes(data: &[u8]) -> Vec<u8> {
+    if data.len() == 1 && data[0] < 0x80 {
+        return data.to_vec();
+    }
+    [rlp_length(0x80, data.len()), data.to_vec()].concat()
+}
+
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    [rlp_length(0xc0, payload.len()), payload].concat()
+}
+
+fn rlp_length(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes = (len as u64).to_be_bytes();
+    let len_bytes = trim_zeros(&len_bytes);
+    [&[offset + 55 + len_bytes.len() as u8][..], len_bytes].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{RecoveryId, Signature};
+
+    use super::*;
+    use crate::abi::{self, Token};
+
+    /// Anvil's first default account
+    const ANVIL_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn addresses_of_known_keys() {
+        let one = parse_private_key(&format!("{:064x}", 1)).unwrap();
+        let anvil = parse_private_key(ANVIL_KEY).unwrap();
+
+        assert_eq!(hex::encode(address(one.verifying_key())), "7e5f4552091a69125d5dfcb7b8c2659029395bdf");
+        assert_eq!(hex::encode(address(anvil.verifying_key())), "f39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+    }
+
+    #[test]
+    fn addresses_must_be_20_bytes() {
+        assert_eq!(parse_address("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap()[0], 0xf3);
+        assert!(parse_address("0xf39fd6e51aad88f6f4ce6ab8827279cfffb922").is_err());
+        assert!(parse_address("0xnothex").is_err());
+    }
+
+    #[test]
+    fn quantities_are_hex_strings() {
+        assert_eq!(quantity(&json!("0x1a")).unwrap(), 26);
+        assert_eq!(quantity(&json!("0x0")).unwrap(), 0);
+        assert!(quantity(&json!(26)).is_err());
+    }
+
+    #[test]
+    fn rlp_matches_the_spec_examples() {
+        assert_eq!(rlp_bytes(b"dog"), hex::decode("83646f67").unwrap());
+        assert_eq!(
+            rlp_list(&[rlp_bytes(b"cat"), rlp_bytes(b"dog")]),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+        assert_eq!(rlp_list(&[]), [0xc0]);
+        assert_eq!(rlp_uint(0), [0x80]);
+        assert_eq!(rlp_uint(15), [0x0f]);
+        assert_eq!(rlp_uint(1024), [0x82, 0x04, 0x00]);
+
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        assert_eq!(rlp_bytes(lorem)[..2], [0xb8, 0x38]);
+        assert_eq!(&rlp_bytes(lorem)[2..], lorem);
+    }
+
+    #[test]
+    fn signed_transaction_recovers_to_the_sender() {
+        let key = parse_private_key(ANVIL_KEY).unwrap();
+        let tx = Transaction {
+            chain_id: 31337,
+            nonce: 7,
+            gas_limit: 100_000,
+            gas_price: GasPrice::Eip1559 {
+                max_fee: 2_000_000_000,
+                max_priority_fee: 1_000_000_000,
+            },
+            to: [0x35; 20],
+            data: vec![0xab, 0xcd],
+        };
+
+        let raw = tx.sign(&key).unwrap();
+
+        let fields = vec![
+            rlp_uint(31337),
+            rlp_uint(7),
+            rlp_uint(1_000_000_000),
+            rlp_uint(2_000_000_000),
+            rlp_uint(100_000),
+            rlp_bytes(&[0x35; 20]),
+            rlp_uint(0),
+            rlp_bytes(&[0xab, 0xcd]),
+            rlp_list(&[]),
+        ];
+        let prehash = keccak256(&[vec![0x02], rlp_list(&fields)].concat());
+        let (signature, recovery_id): (Signature, RecoveryId) = key.sign_prehash_recoverable(&prehash).unwrap();
+        let recovered = VerifyingKey::recover_from_prehash(&prehash, &signature, recovery_id).unwrap();
+        assert_eq!(address(&recovered), address(key.verifying_key()));
+
+        let (r, s) = signature.split_bytes();
+        let mut signed = fields;
+        signed.extend([
+            rlp_uint(recovery_id.to_byte() as u128),
+            rlp_bytes(trim_zeros(&r)),
+            rlp_bytes(trim_zeros(&s)),
+        ]);
+        assert_eq!(raw, [vec![0x02], rlp_list(&signed)].concat());
+    }
+
+    #[test]
+    fn legacy_transaction_v_carries_the_chain_id() {
+        let key = parse_private_key(ANVIL_KEY).unwrap();
+        let tx = Transaction {
+            chain_id: 1,
+            nonce: 0,
+            gas_limit: 21_000,
+            gas_price: GasPrice::Legacy { gas_price: 1 },
+            to: [0x35; 20],
+            data: Vec::new(),
+        };
+
+        let raw = tx.sign(&key).unwrap();
+
+        // A two-byte list header, nonce, gas price, gas limit, to, value and data, then v (37 or
+        // 38 on chain 1), r and s
+        let unsigned = [
+            rlp_uint(0),
+            rlp_uint(1),
+            rlp_uint(21_000),
+            rlp_bytes(&[0x35; 20]),
+            rlp_uint(0),
+            rlp_bytes(&[]),
+        ];
+        let v_offset = 2 + unsigned.concat().len();
+        assert_eq!(raw[0], 0xf8);
+        assert!(matches!(raw[v_offset], 37 | 38), "v = {}", raw[v_offset]);
+    }
+
+    #[test]
+    fn reverts_are_decoded() {
+        let error = abi::encode_call("Error(string)", &[Token::String("Trade not found".to_string())]);
+        let panic = abi::encode_call("Panic(uint256)", &[Token::uint(0x11)]);
+        let custom = abi::encode_call("ProofAfterWindow(uint64,uint256)", &[Token::uint(5), Token::uint(10)]);
+
+        assert_eq!(decode_revert(&error), "Error(\"Trade not found\")");
+        assert_eq!(decode_revert(&panic), "Panic(0x11)");
+        assert_eq!(decode_revert(&custom), "ProofAfterWindow(5, 10)");
+        assert_eq!(decode_revert(&selector("InvalidServerName()")), "InvalidServerName()");
+        assert_eq!(decode_revert(&[0xde, 0xad]), "revert 0xdead");
+        assert_eq!(decode_revert(&[0xde, 0xad, 0xbe, 0xef]), "unknown revert 0xdeadbeef");
+    }
+}
@@ -1,239 +1,316 @@
-mod types;
+mod abi;
+mod binding;
+mod config;
+mod encoding;
+mod eth;
+mod logging;
+mod policy;
+mod signature;
+mod tls;
+
+use steam_zktls::{envelope, ownership, types};
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use clap::Parser;
-use k256::ecdsa::{RecoveryId, Signature as K256Signature, VerifyingKey as K256VerifyingKey};
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use tlsn_core::{
     presentation::{Presentation, PresentationOutput},
+    signing::VerifyingKey,
     CryptoProvider,
 };
-use tracing::info;
+use tracing::{info, info_span, Instrument};
+
+use abi::Token;
+use envelope::ArtifactKind;
+use logging::LogArgs;
+use policy::{Attested, Policy, PolicyArgs};
+use signature::OnChainSignature;
+use tls::STEAM_API_HOST;
+use types::{MultiNotaryProof, P256Proof, ProofCalldata, SolidityProof};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Export TLSNotary presentation for Solidity verification")]
 struct Args {
-    /// Input presentation file
+    /// Input presentation: binary, or a base64/hex JSON wrapper (`-` for stdin)
     #[arg(short, long, default_value = "steam_ownership.presentation.tlsn")]
     input: String,
 
+    /// The input is a multi-notary bundle: write one proof per trusted notary (see --threshold)
+    #[arg(long, default_value = "false")]
+    bundle: bool,
+
     /// Output JSON file
     #[arg(short, long, default_value = "steam_ownership.proof.json")]
     output: String,
 
+    /// App ID the proof must be for
+    #[arg(short, long)]
+    app_id: u32,
+
     /// Show verbose output
     #[arg(short, long, default_value = "false")]
     verbose: bool,
-}
 
-/// Solidity-compatible proof structure
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SolidityProof {
-    /// Notary's Ethereum address (derived from secp256k1 public key)
-    notary_address: String,
-    /// Signature r value (32 bytes)
-    signature_r: String,
-    /// Signature s value (32 bytes)
-    signature_s: String,
-    /// Signature v value (27 or 28)
-    signature_v: u8,
-    /// Message hash that was signed (keccak256)
-    message_hash: String,
-    /// Server name from the proof
-    server_name: String,
-    /// Unix timestamp of the TLS connection
-    timestamp: u64,
-    /// Whether user owns the game (game_count >= 1 from Steam API)
-    owns_game: bool,
-    /// Hash of the revealed transcript data
-    transcript_hash: String,
+    /// Server name the proof must have been generated against
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    expected_server: String,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    /// Trade ID to build `verifyAndResolvePacked` calldata for
+    #[arg(short, long)]
+    trade_id: Option<u64>,
+
+    #[command(flatten)]
+    policy: PolicyArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
-/// Compute Ethereum address from secp256k1 public key
-fn pubkey_to_address(pubkey_bytes: &[u8]) -> Result<[u8; 20]> {
-    // Parse the public key (could be compressed 33 bytes or uncompressed 65 bytes)
-    let verifying_key = K256VerifyingKey::from_sec1_bytes(pubkey_bytes)
-        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
-
-    // Get uncompressed public key (65 bytes: 0x04 || x || y)
-    let uncompressed = verifying_key.to_encoded_point(false);
-    let pubkey_bytes = uncompressed.as_bytes();
-
-    // Ethereum address = last 20 bytes of keccak256(pubkey[1..65])
-    // Skip the 0x04 prefix
-    use tiny_keccak::{Hasher, Keccak};
-    let mut hasher = Keccak::v256();
-    hasher.update(&pubkey_bytes[1..]); // Skip 0x04 prefix
-    let mut hash = [0u8; 32];
-    hasher.finalize(&mut hash);
-
-    let mut address = [0u8; 20];
-    address.copy_from_slice(&hash[12..]);
-    Ok(address)
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
 }
 
-/// Try to recover the v value for ecrecover
-fn find_recovery_id(
-    pubkey_bytes: &[u8],
-    message_hash: &[u8; 32],
-    signature: &K256Signature,
-) -> Result<RecoveryId> {
-    let verifying_key = K256VerifyingKey::from_sec1_bytes(pubkey_bytes)
-        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
-
-    // Try both possible recovery IDs
-    for v in 0..2u8 {
-        let recovery_id = RecoveryId::try_from(v)
-            .map_err(|e| anyhow!("Invalid recovery id: {}", e))?;
-
-        if let Ok(recovered) = K256VerifyingKey::recover_from_prehash(
-            message_hash,
-            signature,
-            recovery_id,
-        ) {
-            if recovered == verifying_key {
-                return Ok(recovery_id);
-            }
-        }
-    }
+async fn run() -> Result<()> {
+    let mut args = Args::parse();
+    args.input = config::artifact_path(&args.input);
+    args.output = config::artifact_path(&args.output);
+    logging::init(&args.log, "info");
 
-    Err(anyhow!("Could not find recovery id for signature"))
+    let span = info_span!("export", job_id = %args.log.job_id(), trade_id = args.trade_id);
+    export(&args).instrument(span).await
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-    let args = Args::parse();
+async fn export(args: &Args) -> Result<()> {
+    let provider = tls::crypto_provider(args.root_cert.as_deref())?;
+    let policy = args.policy.load()?;
+
+    if args.bundle {
+        return export_bundle(args, &provider, &policy).await;
+    }
 
     info!("Loading presentation from {}", args.input);
 
     // Load the presentation
-    let presentation_bytes = tokio::fs::read(&args.input).await?;
-    let presentation: Presentation = bincode::deserialize(&presentation_bytes)?;
+    let presentation: Presentation = encoding::read(ArtifactKind::Presentation, &args.input).await?;
+
+    let (verifying_key, proof) = export_proof(presentation, args, &provider)?;
+    policy.check(&verifying_key, proof.timestamp)?;
+
+    // Write output
+    let json = serde_json::to_string_pretty(&proof)?;
+    tokio::fs::write(&args.output, &json).await?;
+
+    info!("Proof exported to {}", args.output);
+
+    print_proof(&proof, args.trade_id)
+}
+
+/// Export every member of a multi-notary bundle whose notary counts under the policy
+async fn export_bundle(args: &Args, provider: &CryptoProvider, policy: &Policy) -> Result<()> {
+    info!("Loading bundle from {}", args.input);
+
+    let presentations: Vec<Presentation> = encoding::read(ArtifactKind::Bundle, &args.input).await?;
+
+    let exported = presentations
+        .into_iter()
+        .enumerate()
+        .map(|(i, presentation)| {
+            export_proof(presentation, args, provider).map_err(|e| anyhow!("Bundle member {}: {}", i, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let attested: Vec<Attested> = exported
+        .iter()
+        .map(|(verifying_key, proof)| Attested {
+            verifying_key,
+            timestamp: proof.timestamp,
+            owns_game: proof.owns_game,
+        })
+        .collect();
+    let accepted = policy.check_bundle(&attested)?;
+
+    let proofs: Vec<SolidityProof> = exported
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| accepted.contains(i))
+        .map(|(_, (_, proof))| proof)
+        .collect();
+    let bundle = MultiNotaryProof {
+        threshold: policy.threshold,
+        owns_game: proofs[0].owns_game,
+        proofs,
+    };
+
+    let json = serde_json::to_string_pretty(&bundle)?;
+    tokio::fs::write(&args.output, &json).await?;
+
+    info!(
+        "{} proofs (threshold {}) exported to {}",
+        bundle.proofs.len(),
+        bundle.threshold,
+        args.output
+    );
+
+    for proof in &bundle.proofs {
+        print_proof(proof, args.trade_id)?;
+    }
+
+    Ok(())
+}
 
+/// Verify a presentation and build its Solidity proof; returns the notary key for the policy
+fn export_proof(
+    presentation: Presentation,
+    args: &Args,
+    provider: &CryptoProvider,
+) -> Result<(VerifyingKey, SolidityProof)> {
     // Get the verifying key before verification consumes presentation
     let verifying_key = presentation.verifying_key().clone();
 
     if args.verbose {
-        eprintln!("Key algorithm: {}", verifying_key.alg);
-        eprintln!("Key data ({} bytes): {}", verifying_key.data.len(), hex::encode(&verifying_key.data));
+        info!("Key algorithm: {}", verifying_key.alg);
+        info!("Key data ({} bytes): {}", verifying_key.data.len(), hex::encode(&verifying_key.data));
     }
 
+    // Keep the openings for the binding section before verification consumes them
+    let serialized = bcs::to_bytes(&presentation)?;
+
     // Verify and extract data
-    let provider = CryptoProvider::default();
     let PresentationOutput {
         server_name,
         connection_info,
         transcript,
         attestation,
         ..
-    } = presentation.verify(&provider)?;
+    } = presentation.verify(provider)?;
 
     let server_name = server_name.ok_or_else(|| anyhow!("No server name in proof"))?;
     let server_name_str = server_name.as_str().to_string();
 
     // Extract timestamp
     let timestamp = connection_info.time;
-    let connection_time = DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
-        .ok_or_else(|| anyhow!("Invalid timestamp"))?;
-
-    // Extract transcript and determine game ownership
-    let mut partial_transcript = transcript.ok_or_else(|| anyhow!("No transcript in proof"))?;
-    partial_transcript.set_unauthed(b'X');
-    let recv = String::from_utf8_lossy(partial_transcript.received_unsafe());
-
-    let owns_game = recv.contains("\"game_count\":1");
-    let doesnt_own = recv.contains("\"game_count\":0");
-
-    if !owns_game && !doesnt_own {
-        return Err(anyhow!("No valid game_count found in revealed data"));
-    }
-
-    // Hash the transcript data
-    let mut transcript_hasher = Sha256::new();
-    transcript_hasher.update(partial_transcript.received_unsafe());
-    let transcript_hash: [u8; 32] = transcript_hasher.finalize().into();
 
-    // Get signature data from attestation
-    let signature_alg = &attestation.signature.alg;
-    let signature_data = &attestation.signature.data;
+    // Check the server, determine game ownership and hash the revealed transcript
+    let transcript = transcript.ok_or_else(|| anyhow!("No transcript in proof"))?;
+    let result = ownership::verification_result(
+        &server_name_str,
+        &args.expected_server,
+        timestamp,
+        transcript,
+        Some(args.app_id),
+    )?;
 
     if args.verbose {
-        eprintln!("Signature algorithm: {:?}", signature_alg);
-        eprintln!("Signature data ({} bytes): {}", signature_data.len(), hex::encode(signature_data));
-    }
-
-    // Parse signature (should be 64 bytes: r || s)
-    if signature_data.len() != 64 {
-        return Err(anyhow!("Expected 64-byte signature, got {} bytes", signature_data.len()));
+        info!("Signature algorithm: {}", attestation.signature.alg);
+        info!(
+            "Signature data ({} bytes): {}",
+            attestation.signature.data.len(),
+            hex::encode(&attestation.signature.data)
+        );
     }
 
-    let signature = K256Signature::from_slice(signature_data)
-        .map_err(|e| anyhow!("Invalid signature: {}", e))?;
-
-    // The message that was signed is the BCS-serialized header
-    // We need to hash it the same way TLSNotary does
-    let header_bytes = bcs::to_bytes(&attestation.header)?;
+    // On-chain form of the notary's signature; errors out on algorithms no contract can check
+    let signature = signature::on_chain_signature(&attestation)?;
 
     if args.verbose {
-        eprintln!("Header bytes ({} bytes): {}", header_bytes.len(), hex::encode(&header_bytes));
+        info!("Message hash ({}): 0x{}", signature.prehash(), hex::encode(signature.message_hash()));
     }
 
-    // For ecrecover, we need keccak256 of the message
-    use tiny_keccak::{Hasher, Keccak};
-    let mut hasher = Keccak::v256();
-    hasher.update(&header_bytes);
-    let mut message_hash = [0u8; 32];
-    hasher.finalize(&mut message_hash);
-
-    // Compute Ethereum address from public key
-    let notary_address = pubkey_to_address(&verifying_key.data)?;
-
-    // Find recovery ID for ecrecover
-    // Note: TLSNotary uses k256 crate which signs with SHA256 prehash, not keccak256
-    // For proper ecrecover we need to use the same hash the signature was created with
-    let mut sha256_hasher = Sha256::new();
-    sha256_hasher.update(&header_bytes);
-    let sha256_hash: [u8; 32] = sha256_hasher.finalize().into();
-
-    // Try to find recovery ID using SHA256 hash (what was actually signed)
-    let recovery_id = find_recovery_id(&verifying_key.data, &sha256_hash, &signature)?;
-
-    // Ethereum's v is recovery_id + 27
-    let v = recovery_id.to_byte() + 27;
-
-    // For Solidity, we'll pass the SHA256 hash since that's what was signed
-    // The contract will need to be aware that TLSNotary uses SHA256, not keccak256
-
-    let proof = SolidityProof {
-        notary_address: format!("0x{}", hex::encode(notary_address)),
-        signature_r: format!("0x{}", hex::encode(&signature_data[..32])),
-        signature_s: format!("0x{}", hex::encode(&signature_data[32..])),
-        signature_v: v,
-        message_hash: format!("0x{}", hex::encode(&sha256_hash)),
+    let mut proof = SolidityProof {
+        notary_address: None,
+        signature_r: format!("0x{}", hex::encode(signature.r())),
+        signature_s: format!("0x{}", hex::encode(signature.s())),
+        signature_v: None,
+        message_hash: format!("0x{}", hex::encode(signature.message_hash())),
+        message_hash_alg: signature.prehash().to_string(),
         server_name: server_name_str.clone(),
         timestamp,
-        owns_game,
-        transcript_hash: format!("0x{}", hex::encode(&transcript_hash)),
+        owns_game: result.owns_game,
+        transcript_hash: result.transcript_hash,
+        packed_proof: None,
+        calldata: None,
+        p256: None,
+        binding: binding::build(&serialized, &attestation, &connection_info)?,
     };
 
-    // Write output
-    let json = serde_json::to_string_pretty(&proof)?;
-    tokio::fs::write(&args.output, &json).await?;
+    match &signature {
+        OnChainSignature::Ecrecover(signature) => {
+            // Same argument order as SteamOwnershipVerifier.verifyOwnershipPacked decodes
+            let packed_proof = abi::encode(&[
+                Token::FixedBytes(signature.message_hash),
+                Token::uint(signature.v as u64),
+                Token::FixedBytes(signature.r),
+                Token::FixedBytes(signature.s),
+                Token::String(server_name_str.clone()),
+                Token::uint(timestamp),
+                Token::Bool(proof.owns_game),
+                Token::FixedBytes(abi::parse_bytes32(&proof.transcript_hash)?),
+            ]);
+
+            let packed_call = |function: &str, mut args: Vec<Token>| {
+                args.push(Token::Bytes(packed_proof.clone()));
+                format!("0x{}", hex::encode(abi::encode_call(function, &args)))
+            };
+
+            proof.calldata = Some(ProofCalldata {
+                verify_ownership_packed: packed_call("verifyOwnershipPacked(bytes)", vec![]),
+                claim_reward_packed: packed_call("claimRewardPacked(bytes)", vec![]),
+                verify_and_resolve_packed: args.trade_id.map(|trade_id| {
+                    packed_call("verifyAndResolvePacked(uint256,bytes)", vec![Token::uint(trade_id)])
+                }),
+            });
+            proof.notary_address = Some(format!("0x{}", hex::encode(signature.address)));
+            proof.signature_v = Some(signature.v);
+            proof.packed_proof = Some(format!("0x{}", hex::encode(&packed_proof)));
+        }
+        OnChainSignature::P256(signature) => {
+            proof.p256 = Some(P256Proof {
+                qx: format!("0x{}", hex::encode(signature.qx)),
+                qy: format!("0x{}", hex::encode(signature.qy)),
+                precompile_input: format!("0x{}", hex::encode(signature.precompile_input())),
+            });
+        }
+    }
 
-    info!("Proof exported to {}", args.output);
+    // Never write a proof whose fields the signed data does not back
+    binding::check(&proof, provider, &args.expected_server, args.app_id)?;
+
+    Ok((verifying_key, proof))
+}
+
+fn print_proof(proof: &SolidityProof, trade_id: Option<u64>) -> Result<()> {
+    let connection_time = DateTime::<Utc>::from_timestamp(proof.timestamp as i64, 0)
+        .ok_or_else(|| anyhow!("Invalid timestamp"))?;
 
     println!("\n=== Solidity Proof ===");
-    println!("Notary Address:   {}", proof.notary_address);
+    if let Some(notary_address) = &proof.notary_address {
+        println!("Notary Address:   {}", notary_address);
+    }
+    if let Some(p256) = &proof.p256 {
+        println!("Notary Key (P-256): ({}, {})", p256.qx, p256.qy);
+    }
     println!("Server:           {}", proof.server_name);
     println!("Timestamp:        {} ({})", proof.timestamp, connection_time.format("%Y-%m-%d %H:%M:%S UTC"));
     println!("Owns Game:        {}", proof.owns_game);
-    println!("Signature V:      {}", proof.signature_v);
-    println!("Message Hash:     {}", proof.message_hash);
+    if let Some(v) = proof.signature_v {
+        println!("Signature V:      {}", v);
+    }
+    println!("Message Hash:     {} ({})", proof.message_hash, proof.message_hash_alg);
     println!("Transcript Hash:  {}", proof.transcript_hash);
+    if let Some(packed_proof) = &proof.packed_proof {
+        println!("Packed Proof:     {} bytes", (packed_proof.len() - 2) / 2);
+    }
+    if let Some(calldata) = proof.calldata.as_ref().and_then(|c| c.verify_and_resolve_packed.as_ref()) {
+        println!("Trade {} calldata: {}", trade_id.unwrap_or_default(), calldata);
+    }
+    if let Some(p256) = &proof.p256 {
+        println!("P256VERIFY Input: {}", p256.precompile_input);
+    }
 
     Ok(())
 }
@@ -0,0 +1,308 @@
+mod config;
+mod logging;
+
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use http_body_util::Full;
+use hyper::{body::Bytes, service::service_fn, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_rustls::{rustls, TlsAcceptor};
+use tracing::{info, warn};
+
+use steam_zktls::types::Game;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Serve a fake Steam Web API over HTTPS for offline runs")]
+struct Args {
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8443")]
+    listen: String,
+
+    /// Hostname the server certificate is issued for
+    #[arg(long, default_value = "api.steampowered.com")]
+    host: String,
+
+    /// Fixture file (JSON) with users and games (built-in fixture if omitted)
+    #[arg(short, long)]
+    fixture: Option<String>,
+
+    /// Where to write the generated root certificate (PEM)
+    #[arg(long, default_value = "fake_steam.ca.pem")]
+    ca_out: String,
+
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+/// Fixture data served by the fake API
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    /// API key the server accepts (any key if unset)
+    api_key: Option<String>,
+    /// Known users
+    users: Vec<FixtureUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureUser {
+    /// Vanity URL (username)
+    vanity_url: String,
+    /// 64-bit Steam ID
+    steam_id: String,
+    /// Display name
+    persona_name: String,
+    /// Private profiles return an empty GetOwnedGames response
+    #[serde(default)]
+    private: bool,
+    /// Owned games
+    #[serde(default)]
+    games: Vec<Game>,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            users: vec![
+                FixtureUser {
+                    vanity_url: "ohnoitspanda".to_string(),
+                    steam_id: "76561198000000001".to_string(),
+                    persona_name: "panda".to_string(),
+                    private: false,
+                    games: vec![
+                        Game {
+                            appid: 730,
+                            name: Some("Counter-Strike 2".to_string()),
+                            playtime_forever: Some(1234),
+                        },
+                        Game {
+                            appid: 620,
+                            name: Some("Portal 2".to_string()),
+                            playtime_forever: Some(0),
+                        },
+                    ],
+                },
+                FixtureUser {
+                    vanity_url: "privateuser".to_string(),
+                    steam_id: "76561198000000002".to_string(),
+                    persona_name: "hidden".to_string(),
+                    private: true,
+                    games: vec![Game {
+                        appid: 730,
+                        name: Some("Counter-Strike 2".to_string()),
+                        playtime_forever: Some(42),
+                    }],
+                },
+            ],
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, "info");
+
+    let fixture = match &args.fixture {
+        Some(path) => serde_json::from_str(&tokio::fs::read_to_string(path).await?)?,
+        None => Fixture::default(),
+    };
+    let fixture = Arc::new(fixture);
+
+    // Generate a throwaway CA and a server certificate signed by it
+    let (ca_pem, cert_der, key_der) = generate_certificates(&args.host)?;
+    tokio::fs::write(&args.ca_out, ca_pem).await?;
+    info!("Root certificate written to {}", args.ca_out);
+
+    let tls_config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(vec![rustls::Certificate(cert_der)], rustls::PrivateKey(key_der))?;
+    let acceptor = TlsAcceptor::from(Arc::new(tls_config));
+
+    let listener = TcpListener::bind(&args.listen).await?;
+    info!("Fake Steam API for {} listening on {}", args.host, args.listen);
+
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        let fixture = fixture.clone();
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(socket).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+
+            let service = service_fn(move |req| {
+                let fixture = fixture.clone();
+                async move { Ok::<_, Infallible>(handle(&fixture, req)) }
+            });
+
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Generate a CA certificate (PEM) plus a server certificate and key (DER) for `host`
+fn generate_certificates(host: &str) -> Result<(String, Vec<u8>, Vec<u8>)> {
+    let mut ca_params = CertificateParams::new(Vec::new());
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "steam-zktls fake Steam CA");
+    let ca = Certificate::from_params(ca_params)?;
+
+    let server = Certificate::from_params(CertificateParams::new(vec![host.to_string()]))?;
+    let cert_der = server.serialize_der_with_signer(&ca)?;
+
+    Ok((ca.serialize_pem()?, cert_der, server.serialize_private_key_der()))
+}
+
+/// Route a request to the matching fake endpoint
+fn handle(fixture: &Fixture, req: Request<hyper::body::Incoming>) -> Response<Full<Bytes>> {
+    let path = req.uri().path().to_string();
+    let query: HashMap<String, String> = req
+        .uri()
+        .query()
+        .map(|q| {
+            reqwest::Url::parse(&format!("https://fake/?{}", q))
+                .map(|url| url.query_pairs().into_owned().collect())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    info!("{} {}", req.method(), path);
+
+    // Steam answers a bad key with an HTML 403
+    if let Some(expected) = &fixture.api_key {
+        if query.get("key") != Some(expected) {
+            return respond(
+                StatusCode::FORBIDDEN,
+                "text/html",
+                "<html><head><title>Forbidden</title></head><body><h1>Forbidden</h1>Access is denied. Retrying will not help. Please verify your <pre>key=</pre> parameter.</body></html>".to_string(),
+            );
+        }
+    }
+
+    let body = match path.trim_end_matches('/') {
+        "/ISteamUser/ResolveVanityURL/v1" => resolve_vanity_url(fixture, &query),
+        "/IPlayerService/GetOwnedGames/v1" => get_owned_games(fixture, &query),
+        "/ISteamUser/GetPlayerSummaries/v2" => get_player_summaries(fixture, &query),
+        _ => return respond(StatusCode::NOT_FOUND, "text/html", "<html><body>Not Found</body></html>".to_string()),
+    };
+
+    match body {
+        Ok(body) => respond(StatusCode::OK, "application/json; charset=UTF-8", body.to_string()),
+        Err(e) => respond(StatusCode::BAD_REQUEST, "text/html", format!("<html><body>{}</body></html>", e)),
+    }
+}
+
+fn respond(status: StatusCode, content_type: &str, body: String) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response parts are valid")
+}
+
+fn find_user<'a>(fixture: &'a Fixture, steam_id: &str) -> Option<&'a FixtureUser> {
+    fixture.users.iter().find(|u| u.steam_id == steam_id)
+}
+
+fn resolve_vanity_url(fixture: &Fixture, query: &HashMap<String, String>) -> Result<serde_json::Value> {
+    let vanity_url = query
+        .get("vanityurl")
+        .ok_or_else(|| anyhow!("Required parameter 'vanityurl' is missing"))?;
+
+    Ok(match fixture.users.iter().find(|u| &u.vanity_url == vanity_url) {
+        Some(user) => json!({ "response": { "steamid": user.steam_id, "success": 1 } }),
+        None => json!({ "response": { "success": 42, "message": "No match" } }),
+    })
+}
+
+fn get_owned_games(fixture: &Fixture, query: &HashMap<String, String>) -> Result<serde_json::Value> {
+    let steam_id = query
+        .get("steamid")
+        .ok_or_else(|| anyhow!("Required parameter 'steamid' is missing"))?;
+
+    let Some(user) = find_user(fixture, steam_id).filter(|u| !u.private) else {
+        // Private and unknown profiles both come back empty
+        return Ok(json!({ "response": {} }));
+    };
+
+    // appids_filter[0], appids_filter[1], ...
+    let filter: Vec<u32> = query
+        .iter()
+        .filter(|(k, _)| k.starts_with("appids_filter["))
+        .filter_map(|(_, v)| v.parse().ok())
+        .collect();
+
+    let include_appinfo = matches!(
+        query.get("include_appinfo").map(String::as_str),
+        Some("1") | Some("true")
+    );
+
+    let games: Vec<serde_json::Value> = user
+        .games
+        .iter()
+        .filter(|g| filter.is_empty() || filter.contains(&g.appid))
+        .map(|g| {
+            let mut game = json!({
+                "appid": g.appid,
+                "playtime_forever": g.playtime_forever.unwrap_or(0),
+            });
+            if include_appinfo {
+                game["name"] = json!(g.name.clone().unwrap_or_default());
+            }
+            game
+        })
+        .collect();
+
+    Ok(if games.is_empty() {
+        json!({ "response": { "game_count": 0 } })
+    } else {
+        json!({ "response": { "game_count": games.len(), "games": games } })
+    })
+}
+
+fn get_player_summaries(fixture: &Fixture, query: &HashMap<String, String>) -> Result<serde_json::Value> {
+    let steam_ids = query
+        .get("steamids")
+        .ok_or_else(|| anyhow!("Required parameter 'steamids' is missing"))?;
+
+    let players: Vec<serde_json::Value> = steam_ids
+        .split(',')
+        .filter_map(|id| find_user(fixture, id.trim()))
+        .map(|user| {
+            json!({
+                "steamid": user.steam_id,
+                // 1 = private, 3 = public
+                "communityvisibilitystate": if user.private { 1 } else { 3 },
+                "profilestate": 1,
+                "personaname": user.persona_name,
+                "profileurl": format!("https://steamcommunity.com/id/{}/", user.vanity_url),
+            })
+        })
+        .collect();
+
+    Ok(json!({ "response": { "players": players } }))
+}
@@ -0,0 +1,22 @@
+//! Verification core of steam-zktls: everything needed to check a presentation without
+//! tokio, the filesystem or the prover. The binaries build on it, and with the `wasm`
+//! feature it compiles to `wasm32-unknown-unknown` for in-browser verification. The
+//! `prover` feature adds the notaries, including the embedded one tests attest with, and the
+//! transcript disclosure proofs commit to and reveal.
+
+pub mod types;
+
+#[cfg(feature = "verify")]
+pub mod envelope;
+#[cfg(feature = "verify")]
+pub mod ownership;
+#[cfg(feature = "verify")]
+pub mod verify;
+
+#[cfg(feature = "prover")]
+pub mod disclosure;
+#[cfg(feature = "prover")]
+pub mod notary;
+
+#[cfg(feature = "wasm")]
+mod wasm;
@@ -0,0 +1,245 @@
+//! Process-wide Prometheus metrics for proof generation and verification.
+//!
+//! Services expose [`Metrics::render_prometheus`] on `/metrics`; one-shot CLI runs dump
+//! [`Metrics::to_json`] with `--metrics-json`.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tracing::{info_span, Instrument};
+
+/// Upper bounds (seconds) of the phase duration histogram buckets
+const DURATION_BUCKETS: [f64; 12] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+/// Error context naming the phase an operation failed in; it becomes the outcome label
+#[derive(Debug)]
+pub struct PhaseFailed(pub &'static str);
+
+impl fmt::Display for PhaseFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.0)
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative count per bucket in `DURATION_BUCKETS`
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    phase_durations: BTreeMap<&'static str, Histogram>,
+    bytes_sent: u64,
+    bytes_recv: u64,
+    /// (operation, outcome) -> count
+    outcomes: BTreeMap<(&'static str, String), u64>,
+    /// (server, app id, result) -> count
+    verifications: BTreeMap<(String, Option<u32>, &'static str), u64>,
+}
+
+#[derive(Default)]
+pub struct Metrics(Mutex<Registry>);
+
+/// The metrics of this process
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Run one phase of an operation in its own span, recording its duration and tagging its
+/// error with the phase
+pub async fn timed<T>(phase: &'static str, fut: impl Future<Output = Result<T>>) -> Result<T> {
+    let started = Instant::now();
+    let result = fut.instrument(info_span!("phase", phase)).await;
+    global().observe_phase(phase, started.elapsed().as_secs_f64());
+    result.map_err(|e| e.context(PhaseFailed(phase)))
+}
+
+/// [`timed`] for synchronous phases
+pub fn timed_sync<T>(phase: &'static str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let started = Instant::now();
+    let result = info_span!("phase", phase).in_scope(f);
+    global().observe_phase(phase, started.elapsed().as_secs_f64());
+    result.map_err(|e| e.context(PhaseFailed(phase)))
+}
+
+/// Outcome label for a finished operation: `ok`, or `<phase>_failed`
+fn outcome_label<T>(result: &Result<T>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => match e.downcast_ref::<PhaseFailed>() {
+            Some(PhaseFailed(phase)) => format!("{}_failed", phase),
+            None => "failed".to_string(),
+        },
+    }
+}
+
+/// Write the metrics of this process as JSON, for `--metrics-json`
+pub fn write_json(path: &str) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(&global().to_json())?)?;
+    Ok(())
+}
+
+impl Metrics {
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        // Counters stay usable even if a recording thread panicked
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn observe_phase(&self, phase: &'static str, seconds: f64) {
+        self.registry().phase_durations.entry(phase).or_default().observe(seconds);
+    }
+
+    /// Application bytes sent to and received from the server over (MPC-)TLS
+    pub fn add_bytes(&self, sent: usize, recv: usize) {
+        let mut registry = self.registry();
+        registry.bytes_sent += sent as u64;
+        registry.bytes_recv += recv as u64;
+    }
+
+    /// Count a finished `prove` or `verify` operation by outcome
+    pub fn record_outcome<T>(&self, operation: &'static str, result: &Result<T>) {
+        *self
+            .registry()
+            .outcomes
+            .entry((operation, outcome_label(result)))
+            .or_default() += 1;
+    }
+
+    /// Count a successful verification by server, app and result
+    pub fn record_verification(&self, server: &str, app_id: Option<u32>, owns_game: bool) {
+        let result = if owns_game { "owned" } else { "not_owned" };
+        *self
+            .registry()
+            .verifications
+            .entry((server.to_string(), app_id, result))
+            .or_default() += 1;
+    }
+
+    /// Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        out.push_str("# HELP steam_zktls_phase_duration_seconds Time spent in each proving or verification phase\n");
+        out.push_str("# TYPE steam_zktls_phase_duration_seconds histogram\n");
+        for (phase, histogram) in &registry.phase_durations {
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "steam_zktls_phase_duration_seconds_bucket{{phase=\"{}\",le=\"{}\"}} {}",
+                    phase, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "steam_zktls_phase_duration_seconds_bucket{{phase=\"{}\",le=\"+Inf\"}} {}",
+                phase, histogram.count
+            );
+            let _ = writeln!(out, "steam_zktls_phase_duration_seconds_sum{{phase=\"{}\"}} {}", phase, histogram.sum);
+            let _ = writeln!(out, "steam_zktls_phase_duration_seconds_count{{phase=\"{}\"}} {}", phase, histogram.count);
+        }
+
+        out.push_str("# HELP steam_zktls_bytes_total Application bytes exchanged with the server over TLS\n");
+        out.push_str("# TYPE steam_zktls_bytes_total counter\n");
+        let _ = writeln!(out, "steam_zktls_bytes_total{{direction=\"sent\"}} {}", registry.bytes_sent);
+        let _ = writeln!(out, "steam_zktls_bytes_total{{direction=\"recv\"}} {}", registry.bytes_recv);
+
+        out.push_str("# HELP steam_zktls_outcomes_total Finished operations by outcome (ok or the failing phase)\n");
+        out.push_str("# TYPE steam_zktls_outcomes_total counter\n");
+        for ((operation, outcome), count) in &registry.outcomes {
+            let _ = writeln!(
+                out,
+                "steam_zktls_outcomes_total{{operation=\"{}\",outcome=\"{}\"}} {}",
+                operation, outcome, count
+            );
+        }
+
+        out.push_str("# HELP steam_zktls_verifications_total Successful verifications by server, app and result\n");
+        out.push_str("# TYPE steam_zktls_verifications_total counter\n");
+        for ((server, app_id, result), count) in &registry.verifications {
+            let _ = writeln!(
+                out,
+                "steam_zktls_verifications_total{{server=\"{}\",app_id=\"{}\",result=\"{}\"}} {}",
+                escape_label(server),
+                app_id.map(|id| id.to_string()).unwrap_or_default(),
+                result,
+                count
+            );
+        }
+
+        out
+    }
+
+    /// The same metrics as a JSON document
+    pub fn to_json(&self) -> Value {
+        let registry = self.registry();
+
+        let phases: serde_json::Map<String, Value> = registry
+            .phase_durations
+            .iter()
+            .map(|(phase, histogram)| {
+                let buckets: serde_json::Map<String, Value> = DURATION_BUCKETS
+                    .iter()
+                    .zip(histogram.buckets)
+                    .map(|(bound, count)| (bound.to_string(), json!(count)))
+                    .collect();
+                (
+                    phase.to_string(),
+                    json!({ "count": histogram.count, "sumSeconds": histogram.sum, "buckets": buckets }),
+                )
+            })
+            .collect();
+
+        let outcomes: Vec<Value> = registry
+            .outcomes
+            .iter()
+            .map(|((operation, outcome), count)| json!({ "operation": operation, "outcome": outcome, "count": count }))
+            .collect();
+
+        let verifications: Vec<Value> = registry
+            .verifications
+            .iter()
+            .map(|((server, app_id, result), count)| {
+                json!({ "server": server, "appId": app_id, "result": result, "count": count })
+            })
+            .collect();
+
+        json!({
+            "phaseDurations": phases,
+            "bytes": { "sent": registry.bytes_sent, "recv": registry.bytes_recv },
+            "outcomes": outcomes,
+            "verifications": verifications,
+        })
+    }
+}
+
+/// Escape a label value per the text exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,256 @@
+//! Notaries for the prover: a remote notary server, or one embedded in the process for
+//! offline runs and tests that need real attestations.
+
+use std::{fmt, path::Path, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use k256::{pkcs8::DecodePrivateKey, SecretKey};
+use notary_client::{Accepted, NotarizationRequest, NotaryClient};
+use tlsn_common::config::ProtocolConfigValidator;
+use tlsn_core::{attestation::AttestationConfig, signing::SignatureAlgId, CryptoProvider};
+use tlsn_verifier::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::{error, info};
+
+/// Default remote notary host
+pub const DEFAULT_NOTARY_HOST: &str = "127.0.0.1";
+/// Default remote notary port
+pub const DEFAULT_NOTARY_PORT: u16 = 7047;
+/// How long a notary may take to answer its health check before it is skipped
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Byte stream to a notary, remote or in-process
+pub trait NotaryIo: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> NotaryIo for T {}
+
+/// Signature algorithm for the embedded notary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NotaryAlg {
+    /// secp256k1 over a SHA-256 prehash (the notary-server default); verifies off-chain and
+    /// backs receipts, but `export` rejects it
+    Secp256k1,
+    /// secp256k1 over a keccak256 prehash, recomputable on-chain
+    Secp256k1eth,
+    /// P-256 over a SHA-256 prehash, checked on-chain with the RIP-7212 precompile
+    Secp256r1,
+}
+
+impl NotaryAlg {
+    fn id(&self) -> SignatureAlgId {
+        match self {
+            NotaryAlg::Secp256k1 => SignatureAlgId::SECP256K1,
+            NotaryAlg::Secp256k1eth => SignatureAlgId::SECP256K1ETH,
+            NotaryAlg::Secp256r1 => SignatureAlgId::SECP256R1,
+        }
+    }
+}
+
+/// Where the prover gets its notary from
+#[derive(Debug, Clone)]
+pub enum NotaryEndpoint {
+    /// A notary server reached over TCP
+    Remote { host: String, port: u16 },
+    /// A notary running in this process
+    Embedded { signing_key: Vec<u8>, alg: NotaryAlg },
+}
+
+impl NotaryEndpoint {
+    /// Remote notary from NOTARY_HOST / NOTARY_PORT (or defaults)
+    pub fn from_env() -> Self {
+        let host = std::env::var("NOTARY_HOST").unwrap_or_else(|_| DEFAULT_NOTARY_HOST.to_string());
+        let port = std::env::var("NOTARY_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_NOTARY_PORT);
+
+        NotaryEndpoint::Remote { host, port }
+    }
+
+    /// Remote notary from a `host:port` address
+    pub fn parse(addr: &str) -> Result<Self> {
+        let (host, port) = addr
+            .trim()
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Notary address {} is not host:port", addr))?;
+        let port = port
+            .parse()
+            .map_err(|_| anyhow!("Invalid port in notary address {}", addr))?;
+
+        Ok(NotaryEndpoint::Remote { host: host.to_string(), port })
+    }
+}
+
+impl fmt::Display for NotaryEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotaryEndpoint::Remote { host, port } => write!(f, "{}:{}", host, port),
+            NotaryEndpoint::Embedded { .. } => write!(f, "embedded"),
+        }
+    }
+}
+
+/// Check that a remote notary answers `GET /healthcheck`; the embedded notary is always up
+pub async fn probe(endpoint: &NotaryEndpoint) -> Result<()> {
+    let NotaryEndpoint::Remote { host, port } = endpoint else {
+        return Ok(());
+    };
+
+    let response = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()?
+        .get(format!("http://{}:{}/healthcheck", host, port))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Health check returned {}", response.status()));
+    }
+
+    Ok(())
+}
+
+/// Transcript sizes the prover and notary agree on for a session
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptLimits {
+    pub max_sent_data: usize,
+    pub max_recv_data: usize,
+}
+
+/// Open a notarization session and return the byte stream the prover runs MPC-TLS over
+pub async fn connect(endpoint: &NotaryEndpoint, limits: TranscriptLimits) -> Result<Box<dyn NotaryIo>> {
+    match endpoint {
+        NotaryEndpoint::Remote { host, port } => connect_remote(host, *port, limits).await,
+        NotaryEndpoint::Embedded { signing_key, alg } => connect_embedded(signing_key, *alg, limits),
+    }
+}
+
+async fn connect_remote(host: &str, port: u16, limits: TranscriptLimits) -> Result<Box<dyn NotaryIo>> {
+    info!("Connecting to notary server at {}:{}", host, port);
+    let notary_client = NotaryClient::builder()
+        .host(host)
+        .port(port)
+        .enable_tls(false) // No TLS for localhost
+        .build()?;
+
+    let notarization_request = NotarizationRequest::builder()
+        .max_sent_data(limits.max_sent_data)
+        .max_recv_data(limits.max_recv_data)
+        .build()?;
+
+    let Accepted {
+        io: notary_connection,
+        id: _session_id,
+        ..
+    } = notary_client.request_notarization(notarization_request).await?;
+
+    Ok(Box::new(notary_connection))
+}
+
+/// Run the notary side in this runtime, connected to the prover by an in-memory duplex
+fn connect_embedded(
+    signing_key: &[u8],
+    alg: NotaryAlg,
+    limits: TranscriptLimits,
+) -> Result<Box<dyn NotaryIo>> {
+    let mut provider = CryptoProvider::default();
+    match alg {
+        NotaryAlg::Secp256k1 => provider.signer.set_secp256k1(signing_key)?,
+        NotaryAlg::Secp256k1eth => provider.signer.set_secp256k1eth(signing_key)?,
+        NotaryAlg::Secp256r1 => provider.signer.set_secp256r1(signing_key)?,
+    };
+
+    let config_validator = ProtocolConfigValidator::builder()
+        .max_sent_data(limits.max_sent_data)
+        .max_recv_data(limits.max_recv_data)
+        .build()?;
+
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .protocol_config_validator(config_validator)
+            .crypto_provider(provider)
+            .build()?,
+    );
+
+    let attestation_config = AttestationConfig::builder()
+        .supported_signature_algs(vec![alg.id()])
+        .build()?;
+
+    let (prover_io, notary_io) = tokio::io::duplex(1 << 16);
+
+    info!("Starting embedded notary");
+    tokio::spawn(async move {
+        if let Err(e) = verifier.notarize(notary_io.compat(), &attestation_config).await {
+            error!("Embedded notary failed: {}", e);
+        }
+    });
+
+    Ok(Box::new(prover_io))
+}
+
+/// Load a signing key for `alg` from a PKCS#8/SEC1 PEM file, a raw 32-byte or hex file, or
+/// 32-byte hex given inline
+pub fn load_signing_key(spec: &str, alg: NotaryAlg) -> Result<Vec<u8>> {
+    let scalar = if Path::new(spec).exists() {
+        let bytes = std::fs::read(spec).with_context(|| format!("Failed to read signing key file {}", spec))?;
+        if bytes.starts_with(b"-----BEGIN") {
+            let pem = String::from_utf8(bytes)?;
+            pem_scalar(&pem, alg).map_err(|e| anyhow!("Invalid signing key in {}: {}", spec, e))?
+        } else if bytes.len() == 32 {
+            bytes
+        } else {
+            let text = String::from_utf8(bytes).unwrap_or_default();
+            parse_hex_key(text.trim())
+                .ok_or_else(|| anyhow!("Signing key file {} is not PEM, 32 raw bytes or 32-byte hex", spec))?
+        }
+    } else {
+        // Not a file: only a well-formed hex key is taken inline, so a mistyped path is
+        // reported as such
+        parse_hex_key(spec)
+            .ok_or_else(|| anyhow!("Signing key file {} not found (nor is it 32-byte hex)", spec))?
+    };
+
+    // Rejects zero and out-of-range scalars for the curve
+    public_key_hex(&scalar, alg)?;
+    Ok(scalar)
+}
+
+/// Generate a throwaway signing key
+pub fn random_signing_key(alg: NotaryAlg) -> Vec<u8> {
+    match alg {
+        NotaryAlg::Secp256k1 | NotaryAlg::Secp256k1eth => {
+            SecretKey::random(&mut rand::thread_rng()).to_bytes().to_vec()
+        }
+        NotaryAlg::Secp256r1 => p256::SecretKey::random(&mut rand::thread_rng()).to_bytes().to_vec(),
+    }
+}
+
+/// Compressed SEC1 public key for a signing key, for logging
+pub fn public_key_hex(signing_key: &[u8], alg: NotaryAlg) -> Result<String> {
+    let public_key = match alg {
+        NotaryAlg::Secp256k1 | NotaryAlg::Secp256k1eth => {
+            SecretKey::from_slice(signing_key)?.public_key().to_sec1_bytes()
+        }
+        NotaryAlg::Secp256r1 => p256::SecretKey::from_slice(signing_key)?.public_key().to_sec1_bytes(),
+    };
+    Ok(hex::encode(public_key))
+}
+
+fn pem_scalar(pem: &str, alg: NotaryAlg) -> Result<Vec<u8>> {
+    Ok(match alg {
+        NotaryAlg::Secp256k1 | NotaryAlg::Secp256k1eth => SecretKey::from_pkcs8_pem(pem)
+            .or_else(|_| SecretKey::from_sec1_pem(pem))?
+            .to_bytes()
+            .to_vec(),
+        NotaryAlg::Secp256r1 => p256::SecretKey::from_pkcs8_pem(pem)
+            .or_else(|_| p256::SecretKey::from_sec1_pem(pem))?
+            .to_bytes()
+            .to_vec(),
+    })
+}
+
+/// 32-byte hex with an optional `0x` prefix
+fn parse_hex_key(s: &str) -> Option<Vec<u8>> {
+    hex::decode(s.trim_start_matches("0x")).ok().filter(|key| key.len() == 32)
+}
@@ -0,0 +1,363 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use tlsn_core::transcript::PartialTranscript;
+
+use crate::types::VerificationResult;
+
+/// Path of the owned-games request, up to the query
+pub const OWNED_GAMES_PATH: &str = "/IPlayerService/GetOwnedGames/v1/";
+
+/// Query parameters the request must set and reveal, so free-to-play and free-weekend
+/// entries never count as owned
+pub const STRICT_PARAMS: [(&str, &str); 2] = [("include_free_sub", "0"), ("include_played_free_games", "0")];
+
+/// Query parameter filtering the response to the claimed app
+pub const APP_FILTER_PARAM: &str = "appids_filter%5B0%5D";
+
+/// Byte that unauthenticated transcript data is replaced with
+const UNAUTHED: u8 = b'X';
+
+/// A query parameter whose value stays hidden
+#[derive(Debug, Clone, Copy)]
+pub struct HiddenParam {
+    pub name: &'static str,
+    /// Length of the value in characters
+    pub len: usize,
+    /// Characters the value may contain, after lowercase letters are uppercased
+    pub charset: &'static str,
+}
+
+/// Query parameters whose values stay hidden: the Steam API key and the 64-bit Steam ID.
+///
+/// They come last in the query, and every character is sent percent-encoded as `%XY` with only
+/// the low digit `Y` hidden. The revealed high digit `X` is the charset check a verifier can
+/// make: `3` and `4` allow `0`-`?` and `@`-`O` only, so a hidden byte that is a raw `&` can
+/// only start a parameter whose name is made of further `%3Y`/`%4Y` escapes, never a real one.
+/// A hidden `#` or space ends the query inside an escape, leaving a malformed value that Steam
+/// rejects.
+///
+/// The revealed high digits are a deliberate tradeoff: they show whether each key character is
+/// a digit or a letter, about 30 of its 128 bits, leaving roughly 98 bits that only Steam can
+/// check guesses against. The Steam ID leaks nothing, since every digit is `%3Y`.
+pub const HIDDEN_PARAMS: [HiddenParam; 2] = [
+    HiddenParam {
+        name: "key",
+        len: 32,
+        charset: "0123456789ABCDEF",
+    },
+    HiddenParam {
+        name: "steamid",
+        len: 17,
+        charset: "0123456789",
+    },
+];
+
+impl HiddenParam {
+    fn find(name: &str) -> Option<HiddenParam> {
+        HIDDEN_PARAMS.into_iter().find(|param| param.name == name)
+    }
+
+    /// High hex digits of the percent-encoded charset
+    fn high_digits(&self) -> impl Iterator<Item = u8> + '_ {
+        self.charset.bytes().map(|c| b"0123456789ABCDEF"[(c >> 4) as usize])
+    }
+
+    /// `value` uppercased with every character percent-encoded, as the verifier requires. Steam
+    /// API keys are case-insensitive hex, so a lowercase key is sent as its uppercase form.
+    fn encode(&self, value: &str) -> Result<String> {
+        let upper = |c: char| c.to_ascii_uppercase();
+        if value.len() != self.len || !value.chars().all(|c| self.charset.contains(upper(c))) {
+            return Err(anyhow!("{} must be {} characters from {}", self.name, self.len, self.charset));
+        }
+        Ok(value.chars().map(|c| format!("%{:02X}", upper(c) as u32)).collect())
+    }
+
+    /// Check a revealed value: `len` escapes with a revealed `%` and high digit from the
+    /// charset, hiding at most the low digit
+    fn check(&self, value: &str) -> Result<()> {
+        let value = value.as_bytes();
+        if value.len() != 3 * self.len {
+            return Err(anyhow!("Invalid proof - {} is not {} percent-encoded characters", self.name, self.len));
+        }
+
+        for escape in value.chunks_exact(3) {
+            let (percent, high, low) = (escape[0], escape[1], escape[2]);
+            if percent != b'%'
+                || !self.high_digits().any(|digit| digit == high)
+                || !(low == UNAUTHED || low.is_ascii_hexdigit())
+            {
+                return Err(anyhow!(
+                    "Invalid proof - {} hides more than the low digit of each character",
+                    self.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `value` with every byte percent-encoded as `%XY`, the form hidden values are sent in
+pub fn percent_encode(value: &str) -> String {
+    value.bytes().map(|c| format!("%{:02X}", c)).collect()
+}
+
+/// Owned-games request path for `app_id`: the revealed parameters, then the hidden ones encoded
+/// as [`HIDDEN_PARAMS`] describes
+pub fn owned_games_path(api_key: &str, steam_id: &str, app_id: u32) -> Result<String> {
+    // Query only the specific game, which keeps the response small and private
+    let mut path = format!("{}?{}={}&format=json", OWNED_GAMES_PATH, APP_FILTER_PARAM, app_id);
+    // Leave out free subs and played free games explicitly rather than relying on Steam's defaults
+    for (name, value) in STRICT_PARAMS {
+        path.push_str(&format!("&{}={}", name, value));
+    }
+    for (name, value) in [("key", api_key), ("steamid", steam_id)] {
+        let param = HiddenParam::find(name).expect("hidden parameter");
+        path.push_str(&format!("&{}={}", name, param.encode(value)?));
+    }
+
+    Ok(path)
+}
+
+/// Check the server and derive the ownership result from a verified, partially revealed transcript.
+///
+/// The request line must be revealed as [`owned_games_path`] builds it. With `app_id`, it must
+/// filter on that app and an owned result must list it; a `game_count:0` response lists no apps.
+pub fn verification_result(
+    server_name: &str,
+    expected_server: &str,
+    timestamp: u64,
+    mut transcript: PartialTranscript,
+    app_id: Option<u32>,
+) -> Result<VerificationResult> {
+    // Verify it's from the expected server (Steam API unless overridden)
+    if server_name != expected_server {
+        return Err(anyhow!("Invalid server: {} (expected {})", server_name, expected_server));
+    }
+
+    transcript.set_unauthed(UNAUTHED);
+
+    check_request(transcript.sent_unsafe(), app_id)?;
+
+    let transcript_bytes = transcript.received_unsafe();
+    let recv = String::from_utf8_lossy(transcript_bytes);
+
+    // Compute transcript hash (matches Solidity)
+    let transcript_hash: [u8; 32] = Sha256::digest(transcript_bytes).into();
+
+    // Check for game_count in revealed data
+    let owns_game = recv.contains("\"game_count\":1");
+    let doesnt_own = recv.contains("\"game_count\":0");
+
+    if !owns_game && !doesnt_own {
+        return Err(anyhow!("Invalid proof - no game_count revealed"));
+    }
+
+    if let Some(app_id) = app_id.filter(|_| owns_game) {
+        let listed = [format!("\"appid\":{},", app_id), format!("\"appid\":{}}}", app_id)];
+        if !listed.iter().any(|entry| recv.contains(entry.as_str())) {
+            return Err(anyhow!("Invalid proof - response does not list app {}", app_id));
+        }
+    }
+
+    Ok(VerificationResult {
+        owns_game,
+        timestamp,
+        transcript_hash: format!("0x{}", hex::encode(transcript_hash)),
+    })
+}
+
+/// Check the revealed request line: it sets exactly the parameters [`owned_games_path`] does,
+/// each once, with the [`STRICT_PARAMS`] values and the app filter on `app_id` (if given). Only
+/// the low digits of the [`HIDDEN_PARAMS`] values, which come last, may be hidden.
+fn check_request(sent: &[u8], app_id: Option<u32>) -> Result<()> {
+    let end = sent
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .ok_or_else(|| anyhow!("Invalid proof - no request line"))?;
+    let line = String::from_utf8_lossy(&sent[..end]);
+
+    let query = line
+        .strip_prefix(&format!("GET {}?", OWNED_GAMES_PATH))
+        .and_then(|rest| rest.strip_suffix(" HTTP/1.1"))
+        .ok_or_else(|| anyhow!("Invalid proof - request line is not a revealed GetOwnedGames request"))?;
+
+    let hidden = UNAUTHED as char;
+    let mut params = Vec::new();
+    let mut in_hidden = false;
+    for param in query.split('&') {
+        let (name, value) = param
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid proof - malformed query parameter {}", param))?;
+
+        if name.is_empty() || name.contains(hidden) {
+            return Err(anyhow!("Invalid proof - query parameter name is hidden"));
+        }
+        match HiddenParam::find(name) {
+            Some(param) => {
+                param.check(value)?;
+                in_hidden = true;
+            }
+            None if in_hidden => {
+                return Err(anyhow!("Invalid proof - {} follows the hidden parameters", name));
+            }
+            None if value.contains(hidden) => {
+                return Err(anyhow!("Invalid proof - value of {} is hidden", name));
+            }
+            None => {}
+        }
+        params.push((name, value));
+    }
+
+    // Exactly the parameters the prover sets, each once: a second app filter or a copy of a
+    // strict parameter under another name could change what Steam answers
+    let expected: Vec<&str> = [APP_FILTER_PARAM, "format"]
+        .into_iter()
+        .chain(STRICT_PARAMS.iter().map(|&(name, _)| name))
+        .chain(HIDDEN_PARAMS.iter().map(|param| param.name))
+        .collect();
+    if let Some((name, _)) = params.iter().find(|(name, _)| !expected.contains(name)) {
+        return Err(anyhow!("Invalid proof - unexpected query parameter {}", name));
+    }
+    if let Some(&name) = expected.iter().find(|&&name| params.iter().filter(|(n, _)| *n == name).count() != 1) {
+        return Err(anyhow!("Invalid proof - request does not set {} exactly once", name));
+    }
+    let value_of = |name: &str| params.iter().find(|(n, _)| *n == name).map_or("", |&(_, value)| value);
+
+    for &(name, value) in STRICT_PARAMS.iter().chain(&[("format", "json")]) {
+        if value_of(name) != value {
+            return Err(anyhow!("Invalid proof - request does not reveal {}={}", name, value));
+        }
+    }
+
+    let filter = value_of(APP_FILTER_PARAM);
+    let filtered_app: u32 = filter
+        .parse()
+        .map_err(|_| anyhow!("Invalid proof - app filter {} is not an app id", filter))?;
+    if let Some(app_id) = app_id.filter(|&app_id| app_id != filtered_app) {
+        return Err(anyhow!("Invalid proof - request is for app {}, not {}", filtered_app, app_id));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
+
+    const KEY: &str = "0123456789ABCDEF0123456789ABCDEF";
+    const STEAM_ID: &str = "76561198000000001";
+
+    fn path(app_id: u32) -> String {
+        owned_games_path(KEY, STEAM_ID, app_id).unwrap()
+    }
+
+    /// The request for `path` as a verifier sees it, with the bytes at `hidden` (offsets into
+    /// `path`) left out
+    fn disclosed(path: &str, hidden: impl IntoIterator<Item = usize>) -> Vec<u8> {
+        let mut sent = format!("GET {} HTTP/1.1\r\nHost: api.steampowered.com\r\n\r\n", path).into_bytes();
+        for i in hidden {
+            sent["GET ".len() + i] = UNAUTHED;
+        }
+        sent
+    }
+
+    /// Offsets of the value of `name` in `path`
+    fn value_range(path: &str, name: &str) -> Range<usize> {
+        let start = path.find(&format!("&{}=", name)).unwrap() + name.len() + 2;
+        let end = path[start..].find('&').map_or(path.len(), |end| start + end);
+        start..end
+    }
+
+    /// What the prover's disclosure hides: the low digit of every escape in a hidden value
+    fn low_digits(path: &str) -> Vec<usize> {
+        HIDDEN_PARAMS
+            .iter()
+            .flat_map(|param| value_range(path, param.name).skip(2).step_by(3))
+            .collect()
+    }
+
+    #[test]
+    fn disclosed_request_is_accepted() {
+        let path = path(730);
+        let sent = disclosed(&path, low_digits(&path));
+
+        assert!(check_request(&sent, Some(730)).is_ok());
+        assert!(check_request(&sent, None).is_ok());
+    }
+
+    #[test]
+    fn request_for_another_app_is_rejected() {
+        let path = path(570);
+        let err = check_request(&disclosed(&path, low_digits(&path)), Some(730)).unwrap_err();
+
+        assert!(err.to_string().contains("request is for app 570"), "{}", err);
+    }
+
+    #[test]
+    fn second_app_filter_is_rejected() {
+        let path = path(570).replace("&key=", "&appids_filter%5B1%5D=730&key=");
+        let err = check_request(&disclosed(&path, low_digits(&path)), Some(570)).unwrap_err();
+
+        assert!(err.to_string().contains("unexpected query parameter"), "{}", err);
+    }
+
+    #[test]
+    fn parameter_smuggled_in_fixed_length_key_is_rejected() {
+        // A plain 32-character key slot fits `&include_free_sub=1`, hidden along with the key
+        let path = path(730).replace(
+            &format!("&key={}", percent_encode(KEY)),
+            "&key=AAAAAAAAAAAAA&include_free_sub=1",
+        );
+        let key = path.find("&key=").unwrap() + "&key=".len();
+        let sent = disclosed(&path, (key..key + 32).chain(value_range(&path, "steamid").skip(2).step_by(3)));
+
+        let err = check_request(&sent, Some(730)).unwrap_err();
+        assert!(err.to_string().contains("key is not 32 percent-encoded characters"), "{}", err);
+    }
+
+    #[test]
+    fn parameter_smuggled_in_hidden_escapes_is_rejected() {
+        // `&appids_filter%5B1%5D=570` over the escapes after a hidden low digit, all hidden
+        let encoded = percent_encode(STEAM_ID);
+        let smuggled = format!("{}&appids_filter%5B1%5D=570", &encoded[..26]);
+        let path = path(730).replace(&encoded, &smuggled);
+        let steam_id = path.find("&steamid=").unwrap() + "&steamid=".len();
+        let hidden = (steam_id..steam_id + encoded.len()).filter(|i| (i - steam_id) % 3 == 2 || i - steam_id >= 26);
+        let sent = disclosed(&path, hidden.chain(value_range(&path, "key").skip(2).step_by(3)));
+
+        let err = check_request(&sent, Some(730)).unwrap_err();
+        assert!(err.to_string().contains("steamid hides more than the low digit"), "{}", err);
+    }
+
+    #[test]
+    fn revealed_parameter_after_hidden_ones_is_rejected() {
+        let path = format!("{}&include_appinfo=1", path(730));
+        let err = check_request(&disclosed(&path, low_digits(&path)), Some(730)).unwrap_err();
+
+        assert!(err.to_string().contains("include_appinfo follows the hidden parameters"), "{}", err);
+    }
+
+    #[test]
+    fn free_games_setting_must_be_revealed_off() {
+        let path = path(730).replace("include_free_sub=0", "include_free_sub=1");
+        let err = check_request(&disclosed(&path, low_digits(&path)), Some(730)).unwrap_err();
+
+        assert!(err.to_string().contains("does not reveal include_free_sub=0"), "{}", err);
+    }
+
+    #[test]
+    fn lowercase_key_is_sent_uppercased() {
+        let lowercase = owned_games_path(&KEY.to_ascii_lowercase(), STEAM_ID, 730).unwrap();
+
+        assert_eq!(lowercase, path(730));
+    }
+
+    #[test]
+    fn values_outside_the_charset_are_not_sent() {
+        assert!(owned_games_path("0123456789ABCDEF0123456789ABCDEG", STEAM_ID, 730).is_err());
+        assert!(owned_games_path(KEY, "7656119800000000&", 730).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+//! Acceptance policy on top of cryptographic verification: which notary keys are trusted,
+//! over which period, how many of them must agree, and how old a proof may be.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use steam_zktls::verify::VerifiedPresentation;
+use tlsn_core::signing::VerifyingKey;
+use tracing::{info, warn};
+
+/// Policy flags, flattened into the verifying binaries' arguments
+#[derive(clap::Args, Debug)]
+pub struct PolicyArgs {
+    /// Hex notary public key to accept at any time (repeatable or comma-separated)
+    #[arg(long = "trusted-notary-key", env = "TRUSTED_NOTARY_KEYS", value_delimiter = ',')]
+    pub trusted_notary_keys: Vec<String>,
+
+    /// Trust store (JSON) of notary keys with validity windows, for keys that were rotated
+    #[arg(long, env = "NOTARY_TRUST_STORE")]
+    pub trust_store: Option<String>,
+
+    /// Reject proofs whose TLS connection is older than this many seconds
+    #[arg(long, env = "PROOF_MAX_AGE")]
+    pub max_age: Option<u64>,
+
+    /// Distinct trusted notaries a multi-notary bundle needs (more than 1 requires a bundle)
+    #[arg(long, env = "NOTARY_THRESHOLD", default_value = "1")]
+    pub threshold: usize,
+}
+
+/// Notary keys and when each was in service, mirroring `setNotary` rotations on chain
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustStore {
+    pub notaries: Vec<TrustedNotary>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustedNotary {
+    /// Name for logs, e.g. `gefion-2025q1`
+    pub label: String,
+    /// Hex public key, as printed by `verifier --verbose`
+    pub key: String,
+    /// First connection time (Unix seconds) the key may attest to; unbounded if unset
+    #[serde(default)]
+    pub valid_from: Option<u64>,
+    /// Last connection time (Unix seconds) the key may attest to; unbounded if unset
+    #[serde(default)]
+    pub valid_until: Option<u64>,
+}
+
+impl TrustedNotary {
+    fn valid_at(&self, timestamp: u64) -> bool {
+        self.valid_from.map_or(true, |from| timestamp >= from)
+            && self.valid_until.map_or(true, |until| timestamp <= until)
+    }
+}
+
+/// The policy with the trust store loaded
+#[derive(Debug)]
+pub struct Policy {
+    trusted_keys: Vec<String>,
+    trust_store: Option<TrustStore>,
+    max_age: Option<u64>,
+    pub threshold: usize,
+}
+
+/// What one member of a multi-notary bundle attests to
+pub struct Attested<'a> {
+    pub verifying_key: &'a VerifyingKey,
+    pub timestamp: u64,
+    pub owns_game: bool,
+}
+
+impl PolicyArgs {
+    /// Read the trust store, if any
+    pub fn load(&self) -> Result<Policy> {
+        let trust_store = match &self.trust_store {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read trust store {}", path))?;
+                let store: TrustStore =
+                    serde_json::from_str(&text).with_context(|| format!("Invalid trust store {}", path))?;
+                Some(store)
+            }
+            None => None,
+        };
+
+        if self.threshold == 0 {
+            return Err(anyhow!("--threshold must be at least 1"));
+        }
+        // Anyone can sign with as many fresh keys as it takes, so only trusted keys count
+        if self.threshold > 1 && self.trusted_notary_keys.is_empty() && trust_store.is_none() {
+            return Err(anyhow!(
+                "A threshold of {} needs --trusted-notary-key or --trust-store",
+                self.threshold
+            ));
+        }
+
+        Ok(Policy {
+            trusted_keys: self.trusted_notary_keys.iter().map(|k| normalize_key(k)).collect(),
+            trust_store,
+            max_age: self.max_age,
+            threshold: self.threshold,
+        })
+    }
+}
+
+impl Policy {
+    /// Check the notary key against the connection time of a verified presentation, and its age.
+    ///
+    /// Without trusted keys or a trust store any notary is accepted.
+    pub fn check(&self, verifying_key: &VerifyingKey, timestamp: u64) -> Result<()> {
+        if self.threshold > 1 {
+            return Err(anyhow!(
+                "A single presentation cannot meet a threshold of {} notaries; pass a multi-notary bundle",
+                self.threshold
+            ));
+        }
+        self.check_member(verifying_key, timestamp)
+    }
+
+    /// Check a verified multi-notary bundle: members from untrusted (or expired) keys are
+    /// ignored, and the rest must come from at least `threshold` distinct notaries that all
+    /// agree on ownership. Returns the indices of the members that count, one per notary.
+    pub fn check_bundle(&self, members: &[Attested]) -> Result<Vec<usize>> {
+        let mut accepted: Vec<usize> = Vec::new();
+        for (i, member) in members.iter().enumerate() {
+            if let Err(e) = self.check_member(member.verifying_key, member.timestamp) {
+                warn!("Ignoring bundle member {}: {}", i, e);
+                continue;
+            }
+            if accepted.iter().any(|&j| members[j].verifying_key.data == member.verifying_key.data) {
+                warn!("Ignoring bundle member {}: notary already counted", i);
+                continue;
+            }
+            accepted.push(i);
+        }
+
+        if accepted.len() < self.threshold {
+            return Err(anyhow!(
+                "Only {} distinct trusted notaries attest to the claim, {} required",
+                accepted.len(),
+                self.threshold
+            ));
+        }
+        if accepted.iter().any(|&i| members[i].owns_game != members[accepted[0]].owns_game) {
+            return Err(anyhow!("Trusted notaries disagree on ownership"));
+        }
+
+        Ok(accepted)
+    }
+
+    /// [`Policy::check_bundle`] on verified presentations; returns the first member that counts,
+    /// whose result stands for the bundle
+    pub fn accept_bundle(&self, members: Vec<VerifiedPresentation>) -> Result<VerifiedPresentation> {
+        let attested: Vec<Attested> = members
+            .iter()
+            .map(|member| Attested {
+                verifying_key: member.attestation.body.verifying_key(),
+                timestamp: member.result.timestamp,
+                owns_game: member.result.owns_game,
+            })
+            .collect();
+        let first = self.check_bundle(&attested)?[0];
+        info!("Bundle accepted: at least {} trusted notaries agree", self.threshold);
+
+        Ok(members.into_iter().nth(first).expect("accepted index is in range"))
+    }
+
+    /// Notary key and freshness of one presentation
+    fn check_member(&self, verifying_key: &VerifyingKey, timestamp: u64) -> Result<()> {
+        self.check_notary(verifying_key, timestamp)?;
+
+        if let Some(max_age) = self.max_age {
+            let age = (Utc::now().timestamp() as u64).saturating_sub(timestamp);
+            if age > max_age {
+                return Err(anyhow!("Proof is {}s old, more than the {}s allowed", age, max_age));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_notary(&self, verifying_key: &VerifyingKey, timestamp: u64) -> Result<()> {
+        if self.trusted_keys.is_empty() && self.trust_store.is_none() {
+            return Ok(());
+        }
+
+        let key = hex::encode(&verifying_key.data);
+        if self.trusted_keys.contains(&key) {
+            return Ok(());
+        }
+
+        let entries: Vec<&TrustedNotary> = self
+            .trust_store
+            .iter()
+            .flat_map(|store| &store.notaries)
+            .filter(|notary| normalize_key(&notary.key) == key)
+            .collect();
+
+        match entries.iter().find(|notary| notary.valid_at(timestamp)) {
+            Some(notary) => {
+                info!("Notary key {} trusted as {}", key, notary.label);
+                Ok(())
+            }
+            None if entries.is_empty() => Err(anyhow!("Notary key {} is not trusted", key)),
+            None => Err(anyhow!(
+                "Notary key {} ({}) was not valid at connection time {}",
+                key,
+                entries.iter().map(|notary| notary.label.as_str()).collect::<Vec<_>>().join(", "),
+                timestamp
+            )),
+        }
+    }
+}
+
+/// Lowercase hex without a `0x` prefix
+fn normalize_key(key: &str) -> String {
+    key.trim().trim_start_matches("0x").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use tlsn_core::signing::KeyAlgId;
+
+    use super::*;
+
+    // Connection times around the key rotation in `rotation()`
+    const JAN_2025: u64 = 1_735_689_600;
+    const JUL_2025: u64 = 1_751_328_000;
+
+    fn key(byte: u8) -> VerifyingKey {
+        VerifyingKey {
+            alg: KeyAlgId::K256,
+            data: vec![byte; 33],
+        }
+    }
+
+    fn policy(trusted_keys: &[u8], store: Option<&str>) -> Policy {
+        Policy {
+            trusted_keys: trusted_keys.iter().map(|&b| hex::encode(key(b).data)).collect(),
+            trust_store: store.map(|json| serde_json::from_str(json).unwrap()),
+            max_age: None,
+            threshold: 1,
+        }
+    }
+
+    /// Key 0x02 served the first half of 2025 and was rotated to key 0x03
+    fn rotation() -> String {
+        format!(
+            r#"{{"notaries": [
+                {{"label": "old", "key": "0x{}", "valid_from": {}, "valid_until": {}}},
+                {{"label": "new", "key": "{}", "valid_from": {}}}
+            ]}}"#,
+            hex::encode(key(2).data).to_uppercase(),
+            JAN_2025,
+            JUL_2025 - 1,
+            hex::encode(key(3).data),
+            JUL_2025
+        )
+    }
+
+    #[test]
+    fn any_notary_without_trust_configuration() {
+        assert!(policy(&[], None).check(&key(9), JAN_2025).is_ok());
+    }
+
+    #[test]
+    fn trusted_keys_are_accepted_at_any_time() {
+        let policy = policy(&[1], None);
+
+        assert!(policy.check(&key(1), 0).is_ok());
+        assert!(policy.check(&key(1), u64::MAX).is_ok());
+        assert!(policy.check(&key(2), JAN_2025).is_err());
+    }
+
+    #[test]
+    fn trust_store_keys_are_accepted_inside_their_window() {
+        let policy = policy(&[], Some(&rotation()));
+
+        assert!(policy.check(&key(2), JAN_2025).is_ok());
+        assert!(policy.check(&key(2), JUL_2025 - 1).is_ok());
+        assert!(policy.check(&key(3), JUL_2025).is_ok());
+        assert!(policy.check(&key(3), u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn trust_store_keys_are_rejected_outside_their_window() {
+        let policy = policy(&[], Some(&rotation()));
+
+        let before = policy.check(&key(2), JAN_2025 - 1).unwrap_err();
+        let rotated = policy.check(&key(2), JUL_2025).unwrap_err();
+        let early = policy.check(&key(3), JUL_2025 - 1).unwrap_err();
+
+        assert!(before.to_string().contains("(old) was not valid"), "{}", before);
+        assert!(rotated.to_string().contains("(old) was not valid"), "{}", rotated);
+        assert!(early.to_string().contains("(new) was not valid"), "{}", early);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let error = policy(&[], Some(&rotation())).check(&key(4), JAN_2025).unwrap_err();
+
+        assert!(error.to_string().contains("is not trusted"), "{}", error);
+    }
+
+    #[test]
+    fn stale_proofs_are_rejected() {
+        let policy = Policy {
+            max_age: Some(3600),
+            ..policy(&[], None)
+        };
+        let now = Utc::now().timestamp() as u64;
+
+        assert!(policy.check(&key(1), now - 60).is_ok());
+        assert!(policy.check(&key(1), now - 7200).is_err());
+    }
+
+    fn bundle_policy(trusted_keys: &[u8], threshold: usize) -> Policy {
+        Policy {
+            threshold,
+            ..policy(trusted_keys, None)
+        }
+    }
+
+    fn attested(key: &VerifyingKey, owns_game: bool) -> Attested<'_> {
+        Attested {
+            verifying_key: key,
+            timestamp: JAN_2025,
+            owns_game,
+        }
+    }
+
+    #[test]
+    fn single_presentations_cannot_meet_a_threshold() {
+        assert!(bundle_policy(&[1, 2], 2).check(&key(1), JAN_2025).is_err());
+    }
+
+    #[test]
+    fn bundle_counts_only_trusted_notaries() {
+        let (a, b, untrusted) = (key(1), key(2), key(9));
+        let members = [attested(&a, true), attested(&untrusted, true), attested(&b, true)];
+
+        assert_eq!(bundle_policy(&[1, 2], 2).check_bundle(&members).unwrap(), [0, 2]);
+        assert!(bundle_policy(&[1, 2], 2).check_bundle(&members[..2]).is_err());
+    }
+
+    #[test]
+    fn bundle_counts_each_notary_once() {
+        let a = key(1);
+        let members = [attested(&a, true), attested(&a, true)];
+
+        let error = bundle_policy(&[1, 2], 2).check_bundle(&members).unwrap_err();
+
+        assert!(error.to_string().contains("Only 1 distinct trusted notaries"), "{}", error);
+    }
+
+    #[test]
+    fn bundle_rejects_disagreeing_notaries() {
+        let (a, b, c) = (key(1), key(2), key(3));
+        let members = [attested(&a, true), attested(&b, false), attested(&c, true)];
+
+        let error = bundle_policy(&[1, 2, 3], 2).check_bundle(&members).unwrap_err();
+
+        assert!(error.to_string().contains("disagree"), "{}", error);
+    }
+
+    #[test]
+    fn thresholds_need_trusted_keys() {
+        let args = |threshold, trusted_notary_keys: Vec<String>| PolicyArgs {
+            trusted_notary_keys,
+            trust_store: None,
+            max_age: None,
+            threshold,
+        };
+
+        assert!(args(0, Vec::new()).load().is_err());
+        assert!(args(2, Vec::new()).load().is_err());
+        assert!(args(1, Vec::new()).load().is_ok());
+        assert_eq!(args(2, vec!["0xAB".to_string()]).load().unwrap().trusted_keys, ["ab"]);
+    }
+
+    #[test]
+    fn trust_store_rejects_unknown_fields() {
+        let typo = r#"{"notaries": [{"label": "a", "key": "02", "valid_unitl": 1}]}"#;
+
+        assert!(serde_json::from_str::<TrustStore>(typo).is_err());
+    }
+}
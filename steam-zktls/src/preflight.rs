@@ -0,0 +1,173 @@
+//! `prover --dry-run`: the Steam calls of a proof run over plain HTTPS, to catch a wrong name,
+//! a bad key or a private profile before spending an MPC session.
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::{
+    owned_games_path, steam_http_client,
+    tls::{self, ServerTarget},
+    types::{OwnedGamesResponse, PlayerSummariesResponse, VanityUrlResponse},
+};
+
+/// Profile visibility Steam reports for public profiles
+const VISIBILITY_PUBLIC: u32 = 3;
+
+/// Status line and blank line around the headers
+const HTTP_FRAMING_BYTES: usize = "HTTP/1.1 200 OK\r\n\r\n".len();
+
+/// What a proof run would end with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpectedOutcome {
+    /// The proof would show `game_count:1`
+    Owned,
+    /// The proof would show `game_count:0`
+    NotOwned,
+    /// The vanity name does not resolve
+    NotFound,
+    /// The profile is private, so the games list comes back empty
+    PrivateProfile,
+    /// The profile is public but game details are hidden; no `game_count` to reveal
+    GamesHidden,
+    /// The response would not fit the MPC-TLS receive limit
+    ResponseTooLarge,
+}
+
+impl ExpectedOutcome {
+    /// Whether a proof run would produce a result (either way)
+    pub fn provable(self) -> bool {
+        matches!(self, ExpectedOutcome::Owned | ExpectedOutcome::NotOwned)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    pub outcome: ExpectedOutcome,
+    pub provable: bool,
+    pub persona_name: Option<String>,
+    pub public_profile: Option<bool>,
+    pub owns_game: Option<bool>,
+    /// Body size of the filtered `GetOwnedGames` response
+    pub response_bytes: Option<usize>,
+    /// Body plus status line and headers, what MPC-TLS would have to receive
+    pub estimated_recv_bytes: Option<usize>,
+    pub max_recv_bytes: usize,
+}
+
+impl PreflightReport {
+    fn new(outcome: ExpectedOutcome) -> Self {
+        Self {
+            outcome,
+            provable: outcome.provable(),
+            persona_name: None,
+            public_profile: None,
+            owns_game: None,
+            response_bytes: None,
+            estimated_recv_bytes: None,
+            max_recv_bytes: tls::max_recv_data(),
+        }
+    }
+}
+
+/// Run the checks and report the expected outcome of proving `app_id` for `vanity_url`
+pub async fn check(target: &ServerTarget, api_key: &str, vanity_url: &str, app_id: u32) -> Result<PreflightReport> {
+    let (client, base_url) = steam_http_client(target).await?;
+
+    // Same endpoints as the backend's profile check
+    let resolved: VanityUrlResponse = get_json(
+        &client,
+        Zeroizing::new(format!(
+            "{}/ISteamUser/ResolveVanityURL/v1/?key={}&vanityurl={}",
+            base_url, api_key, vanity_url
+        )),
+    )
+    .await?;
+    let Some(steam_id) = resolved.response.steamid.filter(|_| resolved.response.success == 1) else {
+        return Ok(PreflightReport::new(ExpectedOutcome::NotFound));
+    };
+
+    let summaries: PlayerSummariesResponse = get_json(
+        &client,
+        Zeroizing::new(format!(
+            "{}/ISteamUser/GetPlayerSummaries/v2/?key={}&steamids={}",
+            base_url, api_key, steam_id
+        )),
+    )
+    .await?;
+    let Some(player) = summaries.response.players.into_iter().find(|p| p.steamid == steam_id) else {
+        return Ok(PreflightReport::new(ExpectedOutcome::NotFound));
+    };
+
+    let public = player.communityvisibilitystate == VISIBILITY_PUBLIC;
+    let mut report = PreflightReport::new(ExpectedOutcome::PrivateProfile);
+    report.persona_name = player.personaname;
+    report.public_profile = Some(public);
+    if !public {
+        return Ok(report);
+    }
+
+    // The exact request the prover would send over MPC-TLS
+    let path = owned_games_path(api_key, &steam_id, app_id)?;
+    let url = Zeroizing::new(format!("{}{}", base_url, path.as_str()));
+    let response = client
+        .get(url.as_str())
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    check_status(&response)?;
+
+    let header_bytes: usize = response
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + ": \r\n".len())
+        .sum();
+    let body = response.text().await.map_err(reqwest::Error::without_url)?;
+    let estimated_recv_bytes = HTTP_FRAMING_BYTES + header_bytes + body.len();
+
+    report.response_bytes = Some(body.len());
+    report.estimated_recv_bytes = Some(estimated_recv_bytes);
+
+    let owned_games: OwnedGamesResponse = serde_json::from_str(&body)?;
+    report.outcome = if estimated_recv_bytes > report.max_recv_bytes {
+        ExpectedOutcome::ResponseTooLarge
+    } else if owned_games.response.game_count.is_none() {
+        ExpectedOutcome::GamesHidden
+    } else {
+        // Same test as the prover applies to the MPC-TLS response
+        let owns_game = body.contains("\"game_count\":1");
+        report.owns_game = Some(owns_game);
+        if owns_game {
+            ExpectedOutcome::Owned
+        } else {
+            ExpectedOutcome::NotOwned
+        }
+    };
+    report.provable = report.outcome.provable();
+
+    info!("Dry run: expected outcome {:?}", report.outcome);
+
+    Ok(report)
+}
+
+/// GET and decode a Steam API call. The URL carries the API key, so it is wiped once sent and
+/// kept out of errors.
+async fn get_json<T: DeserializeOwned>(client: &reqwest::Client, url: Zeroizing<String>) -> Result<T> {
+    let response = client.get(url.as_str()).send().await.map_err(reqwest::Error::without_url)?;
+    check_status(&response)?;
+    Ok(response.json().await.map_err(reqwest::Error::without_url)?)
+}
+
+fn check_status(response: &reqwest::Response) -> Result<()> {
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status @ (reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) => {
+            Err(anyhow!("Steam API returned {}; is the API key valid?", status))
+        }
+        status => Err(anyhow!("Steam API returned {}", status)),
+    }
+}
@@ -1,11 +1,17 @@
-mod types;
+mod config;
+mod encoding;
+mod logging;
+
+use steam_zktls::{disclosure, envelope, types};
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use tlsn_core::{attestation::Attestation, presentation::Presentation, CryptoProvider, Secrets};
-use tlsn_formats::http::HttpTranscript;
-use tracing::info;
+use tracing::{info, info_span, Instrument};
 
+use encoding::Encoding;
+use logging::LogArgs;
+use envelope::ArtifactKind;
 use types::SteamOwnershipClaim;
 
 #[derive(Parser, Debug)]
@@ -19,66 +25,118 @@ struct Args {
     #[arg(short, long, default_value = "steam_ownership.presentation.tlsn")]
     output: String,
 
+    /// Write a JSON wrapper with base64 or hex data instead of the binary file
+    #[arg(short, long, value_enum, default_value = "binary")]
+    encoding: Encoding,
+
     /// App ID (must match the attestation)
     #[arg(short, long)]
     app_id: u32,
+
+    /// Request header to reveal besides Host (repeatable or comma-separated)
+    #[arg(long = "reveal-header", env = "REVEAL_HEADERS", value_delimiter = ',')]
+    reveal_headers: Vec<String>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+async fn run() -> Result<()> {
     let args = Args::parse();
+    logging::init(&args.log, "info");
 
-    // Load attestation and secrets
-    let attestation_path = format!("{}.attestation.tlsn", args.input);
-    let secrets_path = format!("{}.secrets.tlsn", args.input);
-    let claim_path = format!("{}.claim.json", args.input);
+    let span = info_span!("present", job_id = %args.log.job_id(), app_id = args.app_id);
+    present(&args).instrument(span).await
+}
 
-    info!("Loading attestation from {}", attestation_path);
-    let attestation: Attestation =
-        bincode::deserialize(&tokio::fs::read(&attestation_path).await?)?;
+async fn present(args: &Args) -> Result<()> {
+    let input = config::artifact_path(&args.input);
+    let output = config::artifact_path(&args.output);
+    let claim = load_claim(&input, args.app_id).await?;
 
-    info!("Loading secrets from {}", secrets_path);
-    let secrets: Secrets = bincode::deserialize(&tokio::fs::read(&secrets_path).await?)?;
+    let game_count_pattern = if claim.owns_game {
+        "\"game_count\":1"
+    } else {
+        "\"game_count\":0"
+    };
+
+    info!("Proof will show: {}", game_count_pattern);
+
+    if claim.notaries.is_empty() {
+        let presentation = build_presentation(&input, args).await?;
+        encoding::write(ArtifactKind::Presentation, &output, &presentation, args.encoding).await?;
+        info!("Presentation saved to {}", output);
+    } else {
+        // Multi-notary run: one presentation per notary, bundled in order
+        let mut presentations = Vec::new();
+        for (i, notary) in claim.notaries.iter().enumerate() {
+            info!("Presenting the attestation from notary {} ({})", i, notary);
+            let prefix = format!("{}.{}", input, i);
+            load_claim(&prefix, args.app_id).await?;
+            presentations.push(build_presentation(&prefix, args).await?);
+        }
+        encoding::write(ArtifactKind::Bundle, &output, &presentations, args.encoding).await?;
+        info!("Bundle of {} presentations saved to {}", presentations.len(), output);
+    }
+
+    info!("\nRevealed: {} (for app_id {})", game_count_pattern, claim.app_id);
+    info!("Revealed: include_free_sub=0, include_played_free_games=0 (free games do not count)");
+    info!("Hidden: API key, Steam ID, playtime, all other data");
 
-    // Load claim to verify app_id matches
+    Ok(())
+}
+
+/// Load the claim under `prefix` and check it is for `app_id`
+async fn load_claim(prefix: &str, app_id: u32) -> Result<SteamOwnershipClaim> {
+    let claim_path = format!("{}.claim.json", prefix);
     let claim: SteamOwnershipClaim =
         serde_json::from_str(&tokio::fs::read_to_string(&claim_path).await?)?;
 
-    if claim.app_id != args.app_id {
+    if claim.app_id != app_id {
         return Err(anyhow!(
             "Requested app_id {} does not match attestation app_id {}",
-            args.app_id,
+            app_id,
             claim.app_id
         ));
     }
 
-    // Parse HTTP transcript
-    let transcript = HttpTranscript::parse(secrets.transcript())?;
+    Ok(claim)
+}
 
-    let game_count_pattern = if claim.owns_game {
-        "\"game_count\":1"
-    } else {
-        "\"game_count\":0"
-    };
+/// Build the selective disclosure presentation for the attestation and secrets under `prefix`
+async fn build_presentation(prefix: &str, args: &Args) -> Result<Presentation> {
+    let attestation_path = format!("{}.attestation.tlsn", prefix);
+    let secrets_path = format!("{}.secrets.tlsn", prefix);
 
-    info!("Proof will show: {}", game_count_pattern);
+    info!("Loading attestation from {}", attestation_path);
+    let attestation: Attestation = encoding::read(ArtifactKind::Attestation, &attestation_path).await?;
+
+    info!("Loading secrets from {}", secrets_path);
+    let secrets: Secrets = encoding::read(ArtifactKind::Secrets, &secrets_path).await?;
+
+    // Reveal the request line without the low digits of the percent-encoded API key and
+    // Steam ID, the Host header (proves it's from Steam), any other configured headers, and the
+    // response - it's already filtered to just the one game (~244 bytes, contains only
+    // game_count:0 or game_count:1)
+    let disclosure = disclosure::ownership_disclosure(
+        secrets.transcript().sent(),
+        secrets.transcript().received(),
+        &args.reveal_headers,
+    )?;
 
     // Build transcript proof with selective disclosure
     let mut builder = secrets.transcript_proof_builder();
-
-    // Reveal Host header (proves it's from Steam)
-    let request = &transcript.requests[0];
-    for header in &request.headers {
-        if header.name.as_str().eq_ignore_ascii_case("host") {
-            builder.reveal_sent(header)?;
-        }
+    for range in &disclosure.sent {
+        builder.reveal_sent(range)?;
+    }
+    for range in &disclosure.recv {
+        builder.reveal_recv(range)?;
     }
-
-    // Reveal the response - it's already filtered to just the one game
-    // Response is ~244 bytes, contains only game_count:0 or game_count:1
-    let response = &transcript.responses[0];
-    builder.reveal_recv(response)?;
 
     let transcript_proof = builder.build()?;
 
@@ -90,14 +148,5 @@ async fn main() -> Result<()> {
         .identity_proof(secrets.identity_proof())
         .transcript_proof(transcript_proof);
 
-    let presentation: Presentation = presentation_builder.build()?;
-
-    // Save presentation
-    tokio::fs::write(&args.output, bincode::serialize(&presentation)?).await?;
-
-    info!("Presentation saved to {}", args.output);
-    info!("\nRevealed: {} (for app_id {})", game_count_pattern, claim.app_id);
-    info!("Hidden: API key, Steam ID, playtime, all other data");
-
-    Ok(())
+    Ok(presentation_builder.build()?)
 }
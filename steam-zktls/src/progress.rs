@@ -0,0 +1,165 @@
+//! Progress events and cancellation for a proof run.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::{
+    sync::mpsc::UnboundedSender,
+    task::{JoinError, JoinHandle},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::logging;
+
+/// A milestone of a proof run, in the order they happen
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Notarization session (or the interactive verifier) is set up
+    NotaryConnected,
+    /// Connected to the server through MPC-TLS
+    TlsEstablished,
+    /// The server answered; `owns_game` is what the response says
+    ResponseReceived { owns_game: bool },
+    /// Committing to the transcript and waiting for the notary's signature
+    Notarizing,
+    /// Attestation written (or the proof accepted by the interactive verifier)
+    Done,
+    Failed { error: String },
+    Cancelled,
+}
+
+impl ProgressEvent {
+    /// Name used for the server-sent event
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressEvent::NotaryConnected => "notary_connected",
+            ProgressEvent::TlsEstablished => "tls_established",
+            ProgressEvent::ResponseReceived { .. } => "response_received",
+            ProgressEvent::Notarizing => "notarizing",
+            ProgressEvent::Done => "done",
+            ProgressEvent::Failed { .. } => "failed",
+            ProgressEvent::Cancelled => "cancelled",
+        }
+    }
+
+    /// No events follow this one
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            ProgressEvent::Done | ProgressEvent::Failed { .. } | ProgressEvent::Cancelled
+        )
+    }
+}
+
+/// The run was cancelled before it finished
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "proof run cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// What a proof run reports progress to and takes cancellation from
+#[derive(Clone, Default)]
+pub struct Job {
+    events: Option<UnboundedSender<ProgressEvent>>,
+    cancel: CancellationToken,
+}
+
+impl Job {
+    pub fn new(events: UnboundedSender<ProgressEvent>) -> Self {
+        Self {
+            events: Some(events),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        info!(event = event.name(), "Progress");
+        if let Some(events) = &self.events {
+            // The listener may be gone; the run carries on regardless
+            let _ = events.send(event);
+        }
+    }
+
+    /// Ask the run to stop; it fails with [`Cancelled`] at its next await point
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Drive `fut` until it finishes or the job is cancelled.
+    ///
+    /// On cancellation the future is dropped, which closes the notary and server connections
+    /// it owns and aborts its [`AbortOnDrop`] tasks.
+    pub async fn run<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(Cancelled.into()),
+            result = fut => result,
+        }
+    }
+
+    /// Emit the terminal event for a finished run
+    pub fn finish<T>(&self, outcome: &Result<T>) {
+        self.emit(match outcome {
+            Ok(_) => ProgressEvent::Done,
+            Err(e) if e.is::<Cancelled>() => ProgressEvent::Cancelled,
+            Err(e) => ProgressEvent::Failed { error: logging::scrub(&format!("{:#}", e)) },
+        });
+    }
+}
+
+/// A spawned task that is aborted when its handle is dropped, so a cancelled run leaves
+/// nothing behind
+pub struct AbortOnDrop<T>(pub JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl<T> Future for AbortOnDrop<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix (what the backend's exec timeout sends)
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -1,100 +1,390 @@
-mod types;
-
-use anyhow::{anyhow, Result};
+mod config;
+mod logging;
+mod metrics;
+mod preflight;
+mod progress;
+mod secret;
+mod service;
+mod tls;
+
+use steam_zktls::{disclosure, envelope, notary, ownership, types};
+
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use http_body_util::{BodyExt, Empty};
 use hyper::{body::Bytes, Request, StatusCode};
 use hyper_util::rt::TokioIo;
-use notary_client::{Accepted, NotarizationRequest, NotaryClient};
+use std::{fmt, future::Future};
 use tlsn_common::config::ProtocolConfig;
-use tlsn_core::{request::RequestConfig, transcript::TranscriptCommitConfig};
-use tlsn_formats::http::{DefaultHttpCommitter, HttpCommit, HttpTranscript};
-use tlsn_prover::{Prover, ProverConfig};
+use tlsn_core::{
+    request::RequestConfig,
+    transcript::{Idx, TranscriptCommitConfig},
+};
+use tlsn_prover::{
+    state::{Closed, Setup},
+    Prover, ProverConfig,
+};
 use tokio::net::TcpStream;
 use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
-use tracing::info;
-
+use tracing::{info, info_span, warn, Instrument};
+use zeroize::Zeroizing;
+
+use envelope::ArtifactKind;
+use logging::LogArgs;
+use notary::{NotaryAlg, NotaryEndpoint, TranscriptLimits};
+use progress::{AbortOnDrop, Job, ProgressEvent};
+use secret::Secret;
+use tls::{load_root_certs, ServerTarget, STEAM_API_HOST};
 use types::{OwnedGamesResponse, SteamOwnershipClaim, VanityUrlResponse};
 
-const STEAM_API_HOST: &str = "api.steampowered.com";
-const DEFAULT_NOTARY_HOST: &str = "127.0.0.1";
-const DEFAULT_NOTARY_PORT: u16 = 7047;
-
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Generate zkTLS proof of Steam game ownership")]
 struct Args {
     /// Steam vanity URL (username)
-    #[arg(short, long)]
-    vanity_url: String,
+    #[arg(short, long, required_unless_present = "serve")]
+    vanity_url: Option<String>,
 
     /// Steam app ID to verify ownership of
-    #[arg(short, long)]
-    app_id: u32,
+    #[arg(short, long, required_unless_present = "serve")]
+    app_id: Option<u32>,
+
+    /// Trade this proof is for, to tag logs with
+    #[arg(short, long, env = "TRADE_ID")]
+    trade_id: Option<u64>,
 
-    /// Steam API key (or set STEAM_API_KEY env var)
-    #[arg(short, long, env = "STEAM_API_KEY")]
-    steam_key: String,
+    /// File holding the Steam API key, `-` for stdin (otherwise STEAM_API_KEY is used)
+    #[arg(long, env = "STEAM_API_KEY_FILE")]
+    steam_key_file: Option<String>,
+
+    /// Loaded from --steam-key-file or STEAM_API_KEY after parsing; never a flag
+    #[arg(skip)]
+    steam_key: Secret,
 
     /// Output prefix for attestation and secrets files
     #[arg(short, long, default_value = "steam_ownership")]
     output: String,
+
+    /// Steam Web API host (TLS server name and Host header)
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    server_host: String,
+
+    /// Connect to this host:port instead of <server-host>:443
+    #[arg(long, env = "STEAM_API_ADDR")]
+    server_addr: Option<String>,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    /// Run the notary in-process instead of connecting to NOTARY_HOST:NOTARY_PORT
+    #[arg(long, default_value = "false")]
+    embedded_notary: bool,
+
+    /// Embedded notary signing key: PEM file, raw key file or hex (random if unset)
+    #[arg(long, env = "NOTARY_SIGNING_KEY", requires = "embedded_notary")]
+    notary_key: Option<String>,
+
+    /// Embedded notary signature algorithm (secp256k1eth: keccak256 message hash, secp256r1: RIP-7212)
+    #[arg(
+        long,
+        env = "NOTARY_SIGNATURE_ALG",
+        value_enum,
+        default_value = "secp256k1",
+        requires = "embedded_notary"
+    )]
+    notary_alg: NotaryAlg,
+
+    /// Prove directly to a `verify-interactive` server at this address (no notary, no files)
+    #[arg(long, conflicts_with = "embedded_notary")]
+    interactive: Option<String>,
+
+    /// Run as a service on this address: POST /proofs, follow GET /proofs/{id}/events, DELETE to cancel
+    #[arg(long, conflicts_with_all = ["interactive", "vanity_url", "app_id"])]
+    serve: Option<String>,
+
+    /// Seconds a finished service job and its events stay available before they are dropped
+    #[arg(long, env = "PROVER_JOB_TTL", default_value = "600")]
+    job_ttl: u64,
+
+    /// Only check the name, key, profile visibility and response size over plain HTTPS and
+    /// print the expected outcome; no notary or MPC session
+    #[arg(long, conflicts_with = "serve")]
+    dry_run: bool,
+
+    /// Notary to use (host:port), repeatable or comma-separated; later ones are failovers, tried in
+    /// order when a notary is unhealthy or fails (NOTARY_HOST/NOTARY_PORT if none)
+    #[arg(
+        long = "notary",
+        env = "NOTARY_ENDPOINTS",
+        value_delimiter = ',',
+        conflicts_with = "embedded_notary"
+    )]
+    notaries: Vec<String>,
+
+    /// Attest with each of these notaries (host:port, repeatable or comma-separated) for a k-of-n
+    /// bundle; notary i's files are written under `<output>.<i>`
+    #[arg(
+        long = "multi-notary",
+        env = "MULTI_NOTARY",
+        value_delimiter = ',',
+        conflicts_with_all = ["embedded_notary", "interactive"]
+    )]
+    multi_notary: Vec<String>,
+
+    /// Run the multi-notary sessions concurrently instead of one after another
+    #[arg(long, default_value = "false", requires = "multi_notary")]
+    parallel: bool,
+
+    /// Request header to reveal besides Host, to an interactive verifier or later in `present`,
+    /// which can only reveal what the attestation commits to (repeatable or comma-separated)
+    #[arg(long = "reveal-header", env = "REVEAL_HEADERS", value_delimiter = ',')]
+    reveal_headers: Vec<String>,
+
+    /// Write phase durations, byte counts and the outcome to this file as JSON
+    #[arg(long)]
+    metrics_json: Option<String>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// The Steam query a run proves the answer to
+struct OwnershipQuery<'a> {
+    api_key: &'a str,
+    vanity_url: &'a str,
+    steam_id: &'a str,
+    app_id: u32,
+    /// Request headers disclosed besides Host
+    reveal_headers: &'a [String],
+}
+
+/// One proof to produce
+#[derive(Debug)]
+struct ProofRequest {
+    vanity_url: String,
+    app_id: u32,
+    trade_id: Option<u64>,
+    /// Output prefix for the attestation, secrets and claim files
+    output: String,
+}
+
+fn main() -> Result<()> {
+    // .env and steam-zktls.toml fill in whatever the environment does not set
+    config::load()?;
+    let mut args = Args::parse();
+    logging::init(&args.log, "info");
+    // Removes STEAM_API_KEY from the environment, so it too runs before the runtime's threads exist
+    args.steam_key = secret::load_steam_key(args.steam_key_file.as_deref())?;
+
+    // Errors can quote request URLs, which carry the API key
+    tokio::runtime::Runtime::new()?
+        .block_on(prover_main(args))
+        .map_err(|e| anyhow!(logging::scrub(&format!("{:#}", e))))
+}
+
+async fn prover_main(args: Args) -> Result<()> {
+    if let Some(listen) = args.serve.clone() {
+        let notaries = notary_endpoints(&args)?;
+        return service::serve(args, notaries, &listen).await;
+    }
+
+    let request = ProofRequest {
+        vanity_url: args.vanity_url.clone().expect("required without --serve"),
+        app_id: args.app_id.expect("required without --serve"),
+        trade_id: args.trade_id,
+        output: config::artifact_path(&args.output),
+    };
+
+    if args.dry_run {
+        return dry_run(&args, &request).await;
+    }
+
+    let notaries = notary_endpoints(&args)?;
+
+    // Ctrl-C or SIGTERM tears the run down cleanly instead of killing it mid-MPC
+    let job = Job::default();
+    let interrupt = job.clone();
+    tokio::spawn(async move {
+        progress::shutdown_signal().await;
+        warn!("Interrupted, cancelling the proof run");
+        interrupt.cancel();
+    });
+
+    let span = info_span!(
+        "prove",
+        job_id = %args.log.job_id(),
+        trade_id = request.trade_id,
+        app_id = request.app_id
+    );
+    let outcome = prove(&args, &notaries, &request, &job).instrument(span).await;
+    metrics::global().record_outcome("prove", &outcome);
+    if let Some(path) = &args.metrics_json {
+        metrics::write_json(path)?;
+    }
+
+    outcome
+}
+
+/// Print what a proof run would show without running one; fails if it would not produce a result
+async fn dry_run(args: &Args, request: &ProofRequest) -> Result<()> {
+    let report = preflight::check(
+        &server_target(args),
+        args.steam_key.expose(),
+        &request.vanity_url,
+        request.app_id,
+    )
+    .await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.provable {
+        return Err(anyhow!("A proof run would not produce a result: {:?}", report.outcome));
+    }
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Load .env file if present
-    dotenvy::dotenv().ok();
+/// Use the embedded notary if asked, otherwise the --notary list in failover order, otherwise
+/// NOTARY_HOST/NOTARY_PORT (or defaults)
+fn notary_endpoints(args: &Args) -> Result<Vec<NotaryEndpoint>> {
+    if !args.embedded_notary {
+        if args.notaries.is_empty() {
+            return Ok(vec![NotaryEndpoint::from_env()]);
+        }
+        return args.notaries.iter().map(|addr| NotaryEndpoint::parse(addr)).collect();
+    }
 
-    tracing_subscriber::fmt::init();
-    let args = Args::parse();
+    let signing_key = match &args.notary_key {
+        Some(spec) => notary::load_signing_key(spec, args.notary_alg)?,
+        None => notary::random_signing_key(args.notary_alg),
+    };
+    info!(
+        "Embedded notary {:?} public key: {}",
+        args.notary_alg,
+        notary::public_key_hex(&signing_key, args.notary_alg)?
+    );
+    Ok(vec![NotaryEndpoint::Embedded { signing_key, alg: args.notary_alg }])
+}
 
+/// Produce one proof, reporting progress to `job` and stopping if it is cancelled
+async fn prove(args: &Args, notaries: &[NotaryEndpoint], request: &ProofRequest, job: &Job) -> Result<()> {
+    let outcome = job.run(run(args, notaries, request, job)).await;
+    job.finish(&outcome);
+    outcome
+}
+
+async fn run(args: &Args, notaries: &[NotaryEndpoint], request: &ProofRequest, job: &Job) -> Result<()> {
     info!(
         "Generating ownership proof for vanity_url={}, app_id={}",
-        args.vanity_url, args.app_id
+        request.vanity_url, request.app_id
     );
 
+    let target = server_target(args);
+
     // Step 1: Resolve vanity URL to Steam ID (non-zkTLS, public info)
-    let steam_id = resolve_vanity_url(&args.steam_key, &args.vanity_url).await?;
+    let steam_id = metrics::timed(
+        "resolve",
+        resolve_vanity_url(&target, args.steam_key.expose(), &request.vanity_url),
+    )
+    .await?;
+    logging::redact(&steam_id);
+    logging::redact(&ownership::percent_encode(&steam_id));
     info!("Resolved Steam ID: {}", steam_id);
 
-    // Resolve notary host/port from env vars (or defaults)
-    let notary_host = std::env::var("NOTARY_HOST").unwrap_or_else(|_| DEFAULT_NOTARY_HOST.to_string());
-    let notary_port: u16 = std::env::var("NOTARY_PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(DEFAULT_NOTARY_PORT);
+    let query = OwnershipQuery {
+        api_key: args.steam_key.expose(),
+        vanity_url: &request.vanity_url,
+        steam_id: &steam_id,
+        app_id: request.app_id,
+        reveal_headers: &args.reveal_headers,
+    };
+
+    // Interactive mode: the verifier takes the notary's place and checks the result itself
+    if let Some(verifier_addr) = &args.interactive {
+        prove_interactive(&target, &query, verifier_addr, job).await?;
+        info!("Interactive proof sent to verifier at {}", verifier_addr);
+        return Ok(());
+    }
+
+    // Multi-notary mode: the same query attested by each notary, for a k-of-n bundle
+    if !args.multi_notary.is_empty() {
+        generate_multi_notary(
+            &target,
+            &query,
+            &request.output,
+            &args.multi_notary,
+            args.parallel,
+            job,
+        )
+        .await?;
+        info!("Attestations from {} notaries generated successfully!", args.multi_notary.len());
+        info!("\nRun `present` to bundle them into one multi-notary presentation.");
+        return Ok(());
+    }
 
     // Step 2: Generate zkTLS attestation for owned games API call
-    generate_attestation(
-        &args.steam_key,
-        &args.vanity_url,
-        &steam_id,
-        args.app_id,
-        &args.output,
-        &notary_host,
-        notary_port,
-    )
+    generate_with_failover(notaries, |notary| {
+        generate_attestation(&target, &query, &request.output, notary, job)
+    })
     .await?;
 
     info!("Attestation generated successfully!");
     info!("Files created:");
-    info!("  - {}.attestation.tlsn", args.output);
-    info!("  - {}.secrets.tlsn", args.output);
-    info!("  - {}.claim.json", args.output);
+    info!("  - {}.attestation.tlsn", request.output);
+    info!("  - {}.secrets.tlsn", request.output);
+    info!("  - {}.claim.json", request.output);
     info!("\nRun `present` to create a selective disclosure presentation.");
 
     Ok(())
 }
 
-/// Resolve Steam vanity URL to Steam ID using regular HTTPS
-async fn resolve_vanity_url(api_key: &str, vanity_url: &str) -> Result<String> {
-    let client = reqwest::Client::new();
-    let url = format!(
-        "https://{}/ISteamUser/ResolveVanityURL/v1/?key={}&vanityurl={}",
-        STEAM_API_HOST, api_key, vanity_url
-    );
+fn server_target(args: &Args) -> ServerTarget {
+    ServerTarget {
+        host: args.server_host.clone(),
+        addr: args.server_addr.clone(),
+        root_cert: args.root_cert.clone(),
+    }
+}
+
+/// Build a plain HTTPS client for the target, returning it with the base URL to use
+async fn steam_http_client(target: &ServerTarget) -> Result<(reqwest::Client, String)> {
+    let mut builder = reqwest::Client::builder();
+    let mut base_url = format!("https://{}", target.host);
+
+    if let Some(path) = &target.root_cert {
+        for der in load_root_certs(path)? {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_der(&der)?);
+        }
+    }
+
+    // DNS overrides ignore the port, so it has to go in the URL as well
+    if let Some(addr) = &target.addr {
+        let socket_addr = tokio::net::lookup_host(addr)
+            .await?
+            .next()
+            .ok_or_else(|| anyhow!("Could not resolve server address {}", addr))?;
+        builder = builder.resolve(&target.host, socket_addr);
+        base_url = format!("https://{}:{}", target.host, socket_addr.port());
+    }
+
+    Ok((builder.build()?, base_url))
+}
 
-    let response: VanityUrlResponse = client.get(&url).send().await?.json().await?;
+/// Resolve Steam vanity URL to Steam ID using regular HTTPS
+async fn resolve_vanity_url(target: &ServerTarget, api_key: &str, vanity_url: &str) -> Result<String> {
+    let (client, base_url) = steam_http_client(target).await?;
+    let url = Zeroizing::new(format!(
+        "{}/ISteamUser/ResolveVanityURL/v1/?key={}&vanityurl={}",
+        base_url, api_key, vanity_url
+    ));
+
+    // reqwest errors quote the URL, key included
+    let response: VanityUrlResponse = client
+        .get(url.as_str())
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?
+        .json()
+        .await
+        .map_err(reqwest::Error::without_url)?;
 
     if response.response.success != 1 {
         return Err(anyhow!("Failed to resolve vanity URL"));
@@ -106,129 +396,322 @@ async fn resolve_vanity_url(api_key: &str, vanity_url: &str) -> Result<String> {
         .ok_or_else(|| anyhow!("No Steam ID in response"))
 }
 
-/// Generate zkTLS attestation of game ownership
-async fn generate_attestation(
-    api_key: &str,
-    vanity_url: &str,
-    steam_id: &str,
-    app_id: u32,
-    output_prefix: &str,
-    notary_host: &str,
-    notary_port: u16,
-) -> Result<()> {
-    // Build the request path - query only the specific game using appids_filter
-    // This keeps the response small and private (doesn't expose other games)
-    let request_path = format!(
-        "/IPlayerService/GetOwnedGames/v1/?key={}&steamid={}&appids_filter%5B0%5D={}&format=json",
-        api_key, steam_id, app_id
-    );
+/// Owned-games request path, filtered to a single app. It carries the API key, so it is wiped
+/// when dropped.
+fn owned_games_path(api_key: &str, steam_id: &str, app_id: u32) -> Result<Zeroizing<String>> {
+    Ok(Zeroizing::new(ownership::owned_games_path(api_key, steam_id, app_id)?))
+}
 
-    // Connect to notary server
-    info!("Connecting to notary server at {}:{}", notary_host, notary_port);
-    let notary_client = NotaryClient::builder()
-        .host(notary_host)
-        .port(notary_port)
-        .enable_tls(false) // No TLS for localhost
-        .build()?;
-
-    // Request notarization
-    let notarization_request = NotarizationRequest::builder()
-        .max_sent_data(1024)
-        .max_recv_data(4096) // Filtered response is small
-        .build()?;
-
-    let Accepted {
-        io: notary_connection,
-        id: _session_id,
-        ..
-    } = notary_client.request_notarization(notarization_request).await?;
-
-    info!("Notarization session established");
-
-    // Configure the prover
-    let config = ProverConfig::builder()
-        .server_name(STEAM_API_HOST)
+/// Prover configuration for the target server
+fn prover_config(target: &ServerTarget) -> Result<ProverConfig> {
+    Ok(ProverConfig::builder()
+        .server_name(target.host.as_str())
+        .crypto_provider(target.crypto_provider()?)
         .protocol_config(
             ProtocolConfig::builder()
-                .max_sent_data(1024)
-                .max_recv_data(4096)
+                .max_sent_data(tls::max_sent_data())
+                .max_recv_data(tls::max_recv_data())
                 .build()?,
         )
-        .build()?;
-
-    // Create prover and set up with notary connection
-    let prover = Prover::new(config)
-        .setup(notary_connection.compat())
-        .await?;
-
-    // Connect to Steam API
-    info!("Connecting to Steam API...");
-    let client_socket = TcpStream::connect((STEAM_API_HOST, 443)).await?;
+        .build()?)
+}
 
-    // Bind prover to server connection
-    let (mpc_tls_connection, prover_fut) = prover.connect(client_socket.compat()).await?;
-    let mpc_tls_connection = TokioIo::new(mpc_tls_connection.compat());
+/// Send the owned-games request over MPC-TLS.
+///
+/// Returns the prover once the connection has closed, and whether the game is owned.
+async fn request_owned_games(
+    prover: Prover<Setup>,
+    target: &ServerTarget,
+    request_path: &str,
+    app_id: u32,
+    job: &Job,
+) -> Result<(Prover<Closed>, bool)> {
+    let (mut request_sender, prover_task, connection_task) = metrics::timed("tls_connect", async {
+        // Connect to Steam API
+        info!("Connecting to Steam API at {}...", target.connect_addr());
+        let client_socket = TcpStream::connect(target.connect_addr())
+            .await
+            .context(SteamFailed("Could not connect to Steam API"))?;
+
+        // Bind prover to server connection
+        let (mpc_tls_connection, prover_fut) = prover.connect(client_socket.compat()).await?;
+        let mpc_tls_connection = TokioIo::new(mpc_tls_connection.compat());
+
+        // Spawn prover task; it is aborted if the run is cancelled
+        let prover_task = AbortOnDrop(tokio::spawn(prover_fut));
+
+        // Perform HTTP request through MPC-TLS connection
+        let (request_sender, connection) = hyper::client::conn::http1::handshake(mpc_tls_connection).await?;
+        let connection_task = AbortOnDrop(tokio::spawn(connection));
+
+        anyhow::Ok((request_sender, prover_task, connection_task))
+    })
+    .await?;
+    job.emit(ProgressEvent::TlsEstablished);
+
+    metrics::timed("request", async {
+        let request = Request::builder()
+            .method("GET")
+            .uri(request_path)
+            .header("Host", target.host.as_str())
+            .header("Accept", "application/json")
+            .header("Connection", "close")
+            .body(Empty::<Bytes>::new())?;
+
+        info!("Sending request to Steam API...");
+        let response = request_sender.send_request(request).await?;
+
+        if response.status() != StatusCode::OK {
+            return Err(anyhow!("Steam API returned status: {}", response.status())
+                .context(SteamFailed("Steam API rejected the request")));
+        }
+
+        // Collect response body
+        let body_bytes = response.into_body().collect().await?.to_bytes();
+        let body_str = String::from_utf8(body_bytes.to_vec())?;
+
+        info!("Received response from Steam API ({} bytes)", body_str.len());
+
+        // Parse response to check ownership (filtered API returns game_count: 0 or 1)
+        let owns_game = body_str.contains("\"game_count\":1");
+
+        if owns_game {
+            info!("User OWNS app_id {}", app_id);
+        } else {
+            info!("User does NOT own app_id {}", app_id);
+        }
+        job.emit(ProgressEvent::ResponseReceived { owns_game });
+
+        // Get the prover back after connection closes
+        let prover = prover_task.await??;
+        drop(connection_task);
+
+        anyhow::Ok((prover, owns_game))
+    })
+    .await
+}
 
-    // Spawn prover task
-    let prover_task = tokio::spawn(prover_fut);
+/// Prove ownership to a `verify-interactive` server, which acts as the MPC-TLS verifier
+async fn prove_interactive(
+    target: &ServerTarget,
+    query: &OwnershipQuery<'_>,
+    verifier_addr: &str,
+    job: &Job,
+) -> Result<()> {
+    let request_path = owned_games_path(query.api_key, query.steam_id, query.app_id)?;
 
-    // Perform HTTP request through MPC-TLS connection
-    let (mut request_sender, connection) = hyper::client::conn::http1::handshake(mpc_tls_connection).await?;
-    tokio::spawn(connection);
+    let prover = metrics::timed("verifier_setup", async {
+        info!("Connecting to verifier at {}", verifier_addr);
+        let verifier_socket = TcpStream::connect(verifier_addr).await?;
 
-    let request = Request::builder()
-        .method("GET")
-        .uri(&request_path)
-        .header("Host", STEAM_API_HOST)
-        .header("Accept", "application/json")
-        .header("Connection", "close")
-        .body(Empty::<Bytes>::new())?;
+        anyhow::Ok(Prover::new(prover_config(target)?)
+            .setup(verifier_socket.compat())
+            .await?)
+    })
+    .await?;
+    job.emit(ProgressEvent::NotaryConnected);
+
+    let (prover, _owns_game) = request_owned_games(prover, target, &request_path, query.app_id, job).await?;
+
+    // Reveal the same data a presentation would: Host (plus any configured headers) and the
+    // filtered response
+    let mut prover = prover.start_prove();
+    metrics::global().add_bytes(prover.transcript().sent().len(), prover.transcript().received().len());
+
+    metrics::timed("prove", async {
+        let disclosure = disclosure::ownership_disclosure(
+            prover.transcript().sent(),
+            prover.transcript().received(),
+            query.reveal_headers,
+        )?;
+
+        prover
+            .prove_transcript(Idx::new(disclosure.sent), Idx::new(disclosure.recv))
+            .await?;
+        prover.finalize().await?;
+
+        anyhow::Ok(())
+    })
+    .await
+}
 
-    info!("Sending request to Steam API...");
-    let response = request_sender.send_request(request).await?;
+/// Attest the same query with every notary in `addrs`, writing notary i's files under
+/// `<output_prefix>.<i>` and a claim listing the notaries under `<output_prefix>`
+async fn generate_multi_notary(
+    target: &ServerTarget,
+    query: &OwnershipQuery<'_>,
+    output_prefix: &str,
+    addrs: &[String],
+    parallel: bool,
+    job: &Job,
+) -> Result<()> {
+    let notaries = addrs
+        .iter()
+        .map(|addr| NotaryEndpoint::parse(addr))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Each notary needs its own MPC-TLS session, so Steam is queried once per notary
+    let runs = notaries.iter().zip(addrs).enumerate().map(|(i, (notary, addr))| async move {
+        info!("Attesting with notary {} ({})", i, addr);
+        let prefix = format!("{}.{}", output_prefix, i);
+        generate_attestation(target, query, &prefix, notary, job)
+            .await
+            .with_context(|| format!("Notary {} ({})", i, addr))
+    });
+
+    let results = if parallel {
+        futures::future::try_join_all(runs).await?
+    } else {
+        let mut results = Vec::new();
+        for run in runs {
+            results.push(run.await?);
+        }
+        results
+    };
 
-    if response.status() != StatusCode::OK {
-        return Err(anyhow!("Steam API returned status: {}", response.status()));
+    let owns_game = results[0];
+    if results.iter().any(|&owns| owns != owns_game) {
+        return Err(anyhow!("Notary sessions saw different ownership results: {:?}", results));
     }
 
-    // Collect response body
-    let body_bytes = response.into_body().collect().await?.to_bytes();
-    let body_str = String::from_utf8(body_bytes.to_vec())?;
-
-    info!("Received response from Steam API ({} bytes)", body_str.len());
+    let claim = SteamOwnershipClaim {
+        vanity_url: query.vanity_url.to_string(),
+        steam_id: query.steam_id.to_string(),
+        app_id: query.app_id,
+        owns_game,
+        notaries: addrs.to_vec(),
+        notary: None,
+        notary_key: None,
+    };
+    tokio::fs::write(format!("{}.claim.json", output_prefix), serde_json::to_string_pretty(&claim)?).await?;
 
-    // Parse response to check ownership (filtered API returns game_count: 0 or 1)
-    let owns_game = body_str.contains("\"game_count\":1");
+    Ok(())
+}
 
-    if owns_game {
-        info!("User OWNS app_id {}", app_id);
-    } else {
-        info!("User does NOT own app_id {}", app_id);
+/// Run `attempt` (normally [`generate_attestation`]) with the first notary in `notaries` that
+/// is healthy and completes the session. Failures of the notary or the MPC-TLS session move on
+/// to the next notary; Steam refusing the request does not, since every notary would see the
+/// same.
+async fn generate_with_failover<'a, F, Fut>(notaries: &'a [NotaryEndpoint], mut attempt: F) -> Result<()>
+where
+    F: FnMut(&'a NotaryEndpoint) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let mut failures = Vec::new();
+
+    for notary in notaries {
+        if notaries.len() > 1 {
+            if let Err(e) = notary::probe(notary).await {
+                warn!("Skipping notary {}: {:#}", notary, e);
+                failures.push(format!("{}: {:#}", notary, e));
+                continue;
+            }
+        }
+
+        match attempt(notary).await {
+            Ok(_) => {
+                info!("Attested by notary {}", notary);
+                return Ok(());
+            }
+            Err(e) if !is_notary_failure(&e) => return Err(e),
+            Err(e) => {
+                warn!("Notary {} failed, trying the next one: {:#}", notary, e);
+                failures.push(format!("{}: {:#}", notary, e));
+            }
+        }
     }
 
-    // Get the prover back after connection closes
-    let prover = prover_task.await??;
-
-    // Start notarization
-    let mut prover = prover.start_notarize();
+    Err(anyhow!("All notaries failed: {}", failures.join("; ")))
+}
 
-    // Parse HTTP transcript
-    let transcript = HttpTranscript::parse(prover.transcript())?;
+/// Error context for a failure on Steam's side of the owned-games request, which no other
+/// notary would get past
+#[derive(Debug)]
+struct SteamFailed(&'static str);
 
-    // Commit to the transcript
-    let mut builder = TranscriptCommitConfig::builder(prover.transcript());
+impl fmt::Display for SteamFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
 
-    // Use default HTTP committer to commit to the transcript
-    DefaultHttpCommitter::default().commit_transcript(&mut builder, &transcript)?;
+/// Whether an attestation run failed in a phase the notary takes part in. The TLS connection
+/// and the request run over MPC-TLS with the notary, so they count unless Steam itself failed.
+fn is_notary_failure(error: &anyhow::Error) -> bool {
+    if error.downcast_ref::<SteamFailed>().is_some() {
+        return false;
+    }
+    matches!(
+        error.downcast_ref::<metrics::PhaseFailed>(),
+        Some(metrics::PhaseFailed(
+            "notary_setup" | "tls_connect" | "request" | "start_notarize" | "finalize"
+        ))
+    )
+}
 
-    let config = builder.build()?;
-    prover.transcript_commit(config);
+/// Generate zkTLS attestation of game ownership; returns whether the game is owned
+async fn generate_attestation(
+    target: &ServerTarget,
+    query: &OwnershipQuery<'_>,
+    output_prefix: &str,
+    notary: &NotaryEndpoint,
+    job: &Job,
+) -> Result<bool> {
+    let request_path = owned_games_path(query.api_key, query.steam_id, query.app_id)?;
+
+    let prover = metrics::timed("notary_setup", async {
+        // Connect to notary (remote server or in-process)
+        let limits = TranscriptLimits {
+            max_sent_data: tls::max_sent_data(),
+            max_recv_data: tls::max_recv_data(),
+        };
+        let notary_connection = notary::connect(notary, limits).await?;
+
+        info!("Notarization session established");
+
+        // Create prover and set up with notary connection
+        anyhow::Ok(Prover::new(prover_config(target)?)
+            .setup(notary_connection.compat())
+            .await?)
+    })
+    .await?;
+    job.emit(ProgressEvent::NotaryConnected);
+
+    let (prover, owns_game) = request_owned_games(prover, target, &request_path, query.app_id, job).await?;
+
+    job.emit(ProgressEvent::Notarizing);
+    let prover = metrics::timed_sync("start_notarize", || {
+        // Start notarization
+        let mut prover = prover.start_notarize();
+        metrics::global().add_bytes(prover.transcript().sent().len(), prover.transcript().received().len());
+
+        // Commit to exactly what `present` reveals: a presentation can only open committed
+        // ranges, and the request line is revealed in pieces around the hidden digits
+        let disclosure = disclosure::ownership_disclosure(
+            prover.transcript().sent(),
+            prover.transcript().received(),
+            query.reveal_headers,
+        )?;
+        let mut builder = TranscriptCommitConfig::builder(prover.transcript());
+        for range in &disclosure.sent {
+            builder.commit_sent(range)?;
+        }
+        for range in &disclosure.recv {
+            builder.commit_recv(range)?;
+        }
+
+        let config = builder.build()?;
+        prover.transcript_commit(config);
+
+        Ok(prover)
+    })?;
 
     // Request configuration - specify what we want attested
     let request_config = RequestConfig::default();
 
     // Finalize and get attestation
-    let (attestation, secrets) = prover.finalize(&request_config).await?;
+    let (attestation, secrets) =
+        metrics::timed("finalize", async { anyhow::Ok(prover.finalize(&request_config).await?) }).await?;
 
     info!("Attestation generated");
 
@@ -237,17 +720,102 @@ async fn generate_attestation(
     let secrets_path = format!("{}.secrets.tlsn", output_prefix);
     let claim_path = format!("{}.claim.json", output_prefix);
 
-    tokio::fs::write(&attestation_path, bincode::serialize(&attestation)?).await?;
-    tokio::fs::write(&secrets_path, bincode::serialize(&secrets)?).await?;
+    tokio::fs::write(&attestation_path, envelope::wrap(ArtifactKind::Attestation, &attestation)?).await?;
+    tokio::fs::write(&secrets_path, envelope::wrap(ArtifactKind::Secrets, &secrets)?).await?;
 
     // Save the claim metadata
     let claim = SteamOwnershipClaim {
-        vanity_url: vanity_url.to_string(),
-        steam_id: steam_id.to_string(),
-        app_id,
+        vanity_url: query.vanity_url.to_string(),
+        steam_id: query.steam_id.to_string(),
+        app_id: query.app_id,
         owns_game,
+        notaries: Vec::new(),
+        notary: Some(notary.to_string()),
+        notary_key: Some(hex::encode(&attestation.body.verifying_key().data)),
     };
     tokio::fs::write(&claim_path, serde_json::to_string_pretty(&claim)?).await?;
 
-    Ok(())
+    Ok(owns_game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A remote notary address nothing listens on
+    fn dead_notary() -> NotaryEndpoint {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        NotaryEndpoint::Remote { host: "127.0.0.1".to_string(), port }
+    }
+
+    fn embedded_notary() -> NotaryEndpoint {
+        NotaryEndpoint::Embedded { signing_key: vec![1; 32], alg: NotaryAlg::Secp256k1 }
+    }
+
+    #[tokio::test]
+    async fn dead_first_notary_is_skipped() {
+        let notaries = [dead_notary(), embedded_notary()];
+        let mut attempted = Vec::new();
+
+        let result = generate_with_failover(&notaries, |notary| {
+            attempted.push(notary.to_string());
+            async { Ok(true) }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempted, ["embedded"]);
+    }
+
+    #[tokio::test]
+    async fn mpc_failure_during_the_request_fails_over() {
+        let notaries = [embedded_notary(), embedded_notary()];
+        let mut attempts = 0;
+
+        let result = generate_with_failover(&notaries, |_| {
+            attempts += 1;
+            let first = attempts == 1;
+            async move {
+                if first {
+                    return Err(anyhow!("connection reset").context(metrics::PhaseFailed("request")));
+                }
+                Ok(true)
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn steam_failure_does_not_fail_over() {
+        let notaries = [embedded_notary(), embedded_notary()];
+        let mut attempts = 0;
+
+        let result = generate_with_failover(&notaries, |_| {
+            attempts += 1;
+            async {
+                Err(anyhow!("Steam API returned status: 403 Forbidden")
+                    .context(SteamFailed("Steam API rejected the request"))
+                    .context(metrics::PhaseFailed("request")))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn notary_phases_are_notary_failures() {
+        for phase in ["notary_setup", "tls_connect", "request", "start_notarize", "finalize"] {
+            assert!(is_notary_failure(&anyhow!("boom").context(metrics::PhaseFailed(phase))), "{}", phase);
+        }
+        assert!(!is_notary_failure(&anyhow!("boom").context(metrics::PhaseFailed("resolve"))));
+        let refused = anyhow!("connection refused")
+            .context(SteamFailed("Could not connect to Steam API"))
+            .context(metrics::PhaseFailed("tls_connect"));
+        assert!(!is_notary_failure(&refused));
+    }
 }
@@ -0,0 +1,134 @@
+//! The Steam API key: read from a file, stdin or the environment (never a flag, which `ps`
+//! shows), held in memory that is wiped on drop, and kept out of logs and errors.
+
+use std::{fmt, io::Read};
+
+use anyhow::{anyhow, Context, Result};
+use steam_zktls::ownership;
+use zeroize::Zeroizing;
+
+use crate::logging;
+
+/// Environment variable the key is read from when no file is given
+const STEAM_KEY_ENV: &str = "STEAM_API_KEY";
+
+/// A string that is zeroed when dropped and prints as `[redacted]`
+#[derive(Clone)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    /// The secret itself, for building a request
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Secret {
+    fn default() -> Self {
+        Self(Zeroizing::new(String::new()))
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Load the Steam API key from `file` (`-` for stdin), or from `STEAM_API_KEY` if unset.
+///
+/// The variable is removed from the environment afterwards so child processes do not inherit
+/// it, and the key is registered for redaction. Call before starting the tokio runtime, like
+/// `config::load`.
+pub fn load_steam_key(file: Option<&str>) -> Result<Secret> {
+    let raw = match file {
+        Some("-") => {
+            let mut raw = Zeroizing::new(String::new());
+            std::io::stdin()
+                .read_to_string(&mut raw)
+                .context("Failed to read the Steam API key from stdin")?;
+            raw
+        }
+        Some(path) => Zeroizing::new(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read the Steam API key from {}", path))?,
+        ),
+        None => Zeroizing::new(
+            std::env::var(STEAM_KEY_ENV)
+                .map_err(|_| anyhow!("No Steam API key: set STEAM_API_KEY or pass --steam-key-file"))?,
+        ),
+    };
+    std::env::remove_var(STEAM_KEY_ENV);
+
+    let key = raw.trim();
+    if key.is_empty() {
+        return Err(anyhow!("The Steam API key is empty"));
+    }
+
+    logging::redact(key);
+    // The owned-games request carries it uppercased and percent-encoded
+    logging::redact(&ownership::percent_encode(&Zeroizing::new(key.to_ascii_uppercase())));
+    Ok(Secret(Zeroizing::new(key.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0123456789abcdef0123456789ABCDEF";
+
+    #[test]
+    fn secret_is_hidden_when_printed() {
+        let secret = Secret(Zeroizing::new(KEY.to_string()));
+
+        assert_eq!(format!("{:?}", secret), "[redacted]");
+        assert_eq!(format!("{}", secret), "[redacted]");
+        assert_eq!(secret.expose(), KEY);
+    }
+
+    #[test]
+    fn secret_is_hidden_inside_derived_debug() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Args {
+            steam_key: Secret,
+        }
+
+        let args = Args {
+            steam_key: Secret(Zeroizing::new(KEY.to_string())),
+        };
+
+        assert!(!format!("{:?}", args).contains(KEY));
+    }
+
+    #[test]
+    fn key_file_is_trimmed_and_redacted() {
+        let path = std::env::temp_dir().join(format!("steam-zktls-key-{}", std::process::id()));
+        std::fs::write(&path, format!("{}\n", KEY)).unwrap();
+
+        let secret = load_steam_key(path.to_str()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(secret.expose(), KEY);
+        let encoded = ownership::percent_encode(&KEY.to_ascii_uppercase());
+        let line = logging::scrub(&format!("key={} sent={}", KEY, encoded));
+        assert_eq!(line, "key=[redacted] sent=[redacted]");
+    }
+
+    #[test]
+    fn empty_key_file_is_rejected() {
+        let path = std::env::temp_dir().join(format!("steam-zktls-empty-key-{}", std::process::id()));
+        std::fs::write(&path, " \n").unwrap();
+
+        let result = load_steam_key(path.to_str());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
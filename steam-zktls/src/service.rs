@@ -0,0 +1,318 @@
+//! `prover --serve`: start proofs over HTTP, follow their progress as server-sent events and
+//! cancel them.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
+use hyper::{
+    body::{Bytes, Frame, Incoming},
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, mpsc},
+};
+use tracing::{info, info_span, warn, Instrument};
+
+use crate::{
+    config, metrics,
+    notary::NotaryEndpoint,
+    progress::{Job, ProgressEvent},
+    prove, Args, ProofRequest,
+};
+
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// Body of `POST /proofs`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartProof {
+    vanity_url: String,
+    app_id: u32,
+    #[serde(default)]
+    trade_id: Option<u64>,
+}
+
+/// How often finished jobs past their TTL are dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A proof run and the events it has emitted so far
+struct ProofJob {
+    job: Job,
+    /// Every event so far, replayed to late subscribers
+    history: Mutex<Vec<ProgressEvent>>,
+    /// Events with their index in `history`, so subscribers can tell what they have seen
+    live: broadcast::Sender<(usize, ProgressEvent)>,
+    /// When the run stopped emitting events
+    finished_at: Mutex<Option<Instant>>,
+}
+
+impl ProofJob {
+    fn history(&self) -> MutexGuard<'_, Vec<ProgressEvent>> {
+        self.history.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn finished_at(&self) -> MutexGuard<'_, Option<Instant>> {
+        self.finished_at.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+struct Service {
+    args: Args,
+    notaries: Vec<NotaryEndpoint>,
+    jobs: Mutex<HashMap<String, Arc<ProofJob>>>,
+}
+
+pub async fn serve(args: Args, notaries: Vec<NotaryEndpoint>, listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen).await?;
+    info!("Prover service listening on {}", listen);
+
+    let service = Arc::new(Service {
+        args,
+        notaries,
+        jobs: Mutex::default(),
+    });
+
+    let pruner = service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            pruner.prune();
+        }
+    });
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let service = service.clone();
+
+        tokio::spawn(async move {
+            let handler = service_fn(move |req| {
+                let service = service.clone();
+                async move { Ok::<_, Infallible>(service.handle(req).await) }
+            });
+
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), handler)
+                .await
+            {
+                warn!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+impl Service {
+    async fn handle(self: Arc<Self>, req: Request<Incoming>) -> Response<Body> {
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            (Method::POST, ["proofs"]) => self.start(req).await,
+            (Method::GET, ["proofs", id, "events"]) => self.events(id),
+            (Method::DELETE, ["proofs", id]) => self.cancel(id),
+            (Method::GET, ["metrics"]) => respond(
+                StatusCode::OK,
+                "text/plain; version=0.0.4",
+                metrics::global().render_prometheus(),
+            ),
+            _ => json_response(StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+        }
+    }
+
+    fn job(&self, id: &str) -> Option<Arc<ProofJob>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+    }
+
+    /// Drop jobs that finished more than `--job-ttl` ago; open event streams keep their own handle
+    fn prune(&self) {
+        let ttl = Duration::from_secs(self.args.job_ttl);
+        self.jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, proof_job| proof_job.finished_at().map_or(true, |finished| finished.elapsed() < ttl));
+    }
+
+    /// `POST /proofs`: start a run in the background and return its id
+    async fn start(self: Arc<Self>, req: Request<Incoming>) -> Response<Body> {
+        let body = match req.into_body().collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) => return json_response(StatusCode::BAD_REQUEST, json!({ "error": e.to_string() })),
+        };
+        let start: StartProof = match serde_json::from_slice(&body) {
+            Ok(start) => start,
+            Err(e) => return json_response(StatusCode::BAD_REQUEST, json!({ "error": e.to_string() })),
+        };
+
+        let id = format!("{:016x}", rand::random::<u64>());
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let proof_job = Arc::new(ProofJob {
+            job: Job::new(events_tx),
+            history: Mutex::default(),
+            live: broadcast::channel(16).0,
+            finished_at: Mutex::default(),
+        });
+        self.jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.clone(), proof_job.clone());
+
+        // Record events and fan them out to subscribers until the run finishes. Pushing and
+        // sending under one lock keeps the broadcast in `history` order for `events`.
+        let recorder = proof_job.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events_rx.recv().await {
+                let terminal = event.is_terminal();
+                {
+                    let mut history = recorder.history();
+                    history.push(event.clone());
+                    let _ = recorder.live.send((history.len() - 1, event));
+                }
+                if terminal {
+                    break;
+                }
+            }
+            *recorder.finished_at() = Some(Instant::now());
+        });
+
+        let request = ProofRequest {
+            vanity_url: start.vanity_url,
+            app_id: start.app_id,
+            trade_id: start.trade_id,
+            output: config::artifact_path(&format!("{}_{}", self.args.output, id)),
+        };
+        let response = json!({
+            "jobId": id,
+            "events": format!("/proofs/{}/events", id),
+            "output": request.output,
+        });
+
+        let span = info_span!(
+            "prove",
+            job_id = %id,
+            trade_id = request.trade_id,
+            app_id = request.app_id
+        );
+        tokio::spawn(
+            async move {
+                let outcome = prove(&self.args, &self.notaries, &request, &proof_job.job).await;
+                metrics::global().record_outcome("prove", &outcome);
+            }
+            .instrument(span),
+        );
+
+        json_response(StatusCode::ACCEPTED, response)
+    }
+
+    /// `GET /proofs/{id}/events`: past events, then live ones until the run finishes
+    fn events(&self, id: &str) -> Response<Body> {
+        let Some(proof_job) = self.job(id) else {
+            return json_response(StatusCode::NOT_FOUND, json!({ "error": "unknown job" }));
+        };
+
+        // Subscribe under the history lock so no event is missed or sent twice
+        let (history, live) = {
+            let history = proof_job.history();
+            (history.clone(), proof_job.live.subscribe())
+        };
+        let finished = history.last().is_some_and(ProgressEvent::is_terminal);
+
+        let state = Subscriber {
+            proof_job,
+            live,
+            pending: history.into(),
+            sent: 0,
+            finished,
+        };
+        let events = stream::unfold(state, |mut state| async move {
+            let event = state.next().await?;
+            Some((event, state))
+        });
+
+        let frames = events.map(|event| {
+            let data = serde_json::to_string(&event).unwrap_or_default();
+            Ok(Frame::data(Bytes::from(format!("event: {}\ndata: {}\n\n", event.name(), data))))
+        });
+
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .body(StreamBody::new(frames).boxed_unsync())
+            .expect("static response parts are valid")
+    }
+
+    /// `DELETE /proofs/{id}`: cancel a run; its event stream ends with `cancelled`
+    fn cancel(&self, id: &str) -> Response<Body> {
+        match self.job(id) {
+            Some(proof_job) => {
+                proof_job.job.cancel();
+                json_response(StatusCode::ACCEPTED, json!({ "jobId": id, "cancelling": true }))
+            }
+            None => json_response(StatusCode::NOT_FOUND, json!({ "error": "unknown job" })),
+        }
+    }
+}
+
+/// One `events` stream: the replayed history, then live events, resyncing from `history` when
+/// the broadcast buffer overflowed
+struct Subscriber {
+    proof_job: Arc<ProofJob>,
+    live: broadcast::Receiver<(usize, ProgressEvent)>,
+    /// Events to send before waiting on `live` again
+    pending: VecDeque<ProgressEvent>,
+    /// Number of events sent, i.e. the history index `pending` continues from
+    sent: usize,
+    finished: bool,
+}
+
+impl Subscriber {
+    async fn next(&mut self) -> Option<ProgressEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                self.sent += 1;
+                self.finished = event.is_terminal();
+                return Some(event);
+            }
+            if self.finished {
+                return None;
+            }
+
+            match self.live.recv().await {
+                // Already replayed from history
+                Ok((index, _)) if index < self.sent => continue,
+                Ok((_, event)) => self.pending.push_back(event),
+                // Missed live events are still in the history
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Event subscriber lagged by {} events, resyncing from history", missed);
+                    self.pending.extend(self.proof_job.history()[self.sent..].iter().cloned());
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+fn respond(status: StatusCode, content_type: &str, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .body(Full::new(Bytes::from(body)).boxed_unsync())
+        .expect("static response parts are valid")
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    respond(status, "application/json", body.to_string())
+}
@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Result};
+use k256::ecdsa::{RecoveryId, Signature as K256Signature, VerifyingKey as K256VerifyingKey};
+use p256::ecdsa::{
+    signature::hazmat::PrehashVerifier, Signature as P256Signature, VerifyingKey as P256VerifyingKey,
+};
+use sha2::{Digest, Sha256};
+use tlsn_core::{attestation::Attestation, signing::SignatureAlgId};
+
+use crate::{abi, eth};
+
+/// Hash the notary applied to the BCS header before signing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prehash {
+    /// `secp256k1` and `secp256r1`
+    Sha256,
+    /// `secp256k1eth`: matches what Solidity's `keccak256` produces
+    Keccak256,
+}
+
+impl Prehash {
+    pub fn digest(&self, data: &[u8]) -> [u8; 32] {
+        match self {
+            Prehash::Sha256 => Sha256::digest(data).into(),
+            Prehash::Keccak256 => abi::keccak256(data),
+        }
+    }
+}
+
+impl std::fmt::Display for Prehash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Prehash::Sha256 => write!(f, "sha256"),
+            Prehash::Keccak256 => write!(f, "keccak256"),
+        }
+    }
+}
+
+/// Notary signature as `ecrecover(messageHash, v, r, s)` expects it
+#[derive(Debug, Clone)]
+pub struct EcrecoverSignature {
+    pub prehash: Prehash,
+    pub message_hash: [u8; 32],
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// 27 or 28
+    pub v: u8,
+    /// Address `ecrecover` returns, i.e. the notary's Ethereum address
+    pub address: [u8; 20],
+}
+
+/// secp256r1 notary signature as the RIP-7212 `P256VERIFY` precompile takes it
+#[derive(Debug, Clone)]
+pub struct P256PrecompileSignature {
+    /// SHA-256 of the BCS header
+    pub message_hash: [u8; 32],
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// Uncompressed notary public key coordinates
+    pub qx: [u8; 32],
+    pub qy: [u8; 32],
+}
+
+impl P256PrecompileSignature {
+    /// `hash || r || s || qx || qy`, the 160-byte precompile input
+    pub fn precompile_input(&self) -> Vec<u8> {
+        [self.message_hash, self.r, self.s, self.qx, self.qy].concat()
+    }
+}
+
+/// Notary signature in the form its algorithm can be checked on-chain
+#[derive(Debug, Clone)]
+pub enum OnChainSignature {
+    /// secp256k1 / secp256k1eth, checked with `ecrecover`
+    Ecrecover(EcrecoverSignature),
+    /// secp256r1, checked with the RIP-7212 precompile
+    P256(P256PrecompileSignature),
+}
+
+impl OnChainSignature {
+    pub fn message_hash(&self) -> [u8; 32] {
+        match self {
+            OnChainSignature::Ecrecover(sig) => sig.message_hash,
+            OnChainSignature::P256(sig) => sig.message_hash,
+        }
+    }
+
+    pub fn r(&self) -> [u8; 32] {
+        match self {
+            OnChainSignature::Ecrecover(sig) => sig.r,
+            OnChainSignature::P256(sig) => sig.r,
+        }
+    }
+
+    pub fn s(&self) -> [u8; 32] {
+        match self {
+            OnChainSignature::Ecrecover(sig) => sig.s,
+            OnChainSignature::P256(sig) => sig.s,
+        }
+    }
+
+    pub fn prehash(&self) -> Prehash {
+        match self {
+            OnChainSignature::Ecrecover(sig) => sig.prehash,
+            OnChainSignature::P256(_) => Prehash::Sha256,
+        }
+    }
+}
+
+/// Prehash of a secp256k1 signature algorithm, i.e. the hash `ecrecover` recovers the notary from
+pub fn ecrecover_prehash(alg: &SignatureAlgId) -> Result<Prehash> {
+    if *alg == SignatureAlgId::SECP256K1 {
+        Ok(Prehash::Sha256)
+    } else if *alg == SignatureAlgId::SECP256K1ETH {
+        Ok(Prehash::Keccak256)
+    } else {
+        Err(anyhow!(
+            "Notary signature algorithm {} is not supported by ecrecover: it needs secp256k1 or secp256k1eth",
+            alg
+        ))
+    }
+}
+
+/// Convert the attestation's notary signature into whatever its algorithm needs on-chain.
+///
+/// The contracts tie `messageHash` to the header with `keccak256`, so of the secp256k1
+/// algorithms only `secp256k1eth` is accepted; a SHA-256 prehashed `secp256k1` signature would
+/// still pass `ecrecover`, but for a hash the contract cannot recompute.
+pub fn on_chain_signature(attestation: &Attestation) -> Result<OnChainSignature> {
+    let alg = &attestation.signature.alg;
+    if *alg == SignatureAlgId::SECP256R1 {
+        Ok(OnChainSignature::P256(p256_signature(attestation)?))
+    } else if *alg == SignatureAlgId::SECP256K1ETH {
+        Ok(OnChainSignature::Ecrecover(ecrecover_signature(attestation)?))
+    } else if *alg == SignatureAlgId::SECP256K1 {
+        Err(anyhow!(
+            "Notary signature algorithm {} signs a SHA-256 prehash the contracts cannot check with \
+             ecrecover: use a secp256k1eth (keccak256) or secp256r1 notary",
+            alg
+        ))
+    } else {
+        Err(anyhow!(
+            "Notary signature algorithm {} is not supported on-chain: \
+             ecrecover needs secp256k1eth, P256VERIFY needs secp256r1",
+            alg
+        ))
+    }
+}
+
+/// Convert a secp256r1 notary signature into `P256VERIFY` arguments
+pub fn p256_signature(attestation: &Attestation) -> Result<P256PrecompileSignature> {
+    let data = &attestation.signature.data;
+    if data.len() != 64 {
+        return Err(anyhow!("Expected 64-byte secp256r1 signature, got {} bytes", data.len()));
+    }
+    let signature = P256Signature::from_slice(data).map_err(|e| anyhow!("Invalid signature: {}", e))?;
+
+    let verifying_key = P256VerifyingKey::from_sec1_bytes(&attestation.body.verifying_key().data)
+        .map_err(|e| anyhow!("Invalid notary public key: {}", e))?;
+
+    // The notary signs SHA-256 of the BCS-serialized header
+    let message_hash = Prehash::Sha256.digest(&bcs::to_bytes(&attestation.header)?);
+    verifying_key
+        .verify_prehash(&message_hash, &signature)
+        .map_err(|_| anyhow!("secp256r1 signature does not verify over the header hash"))?;
+
+    let point = verifying_key.to_encoded_point(false);
+    let coordinate = |c: Option<&p256::FieldBytes>| -> Result<[u8; 32]> {
+        Ok(c.ok_or_else(|| anyhow!("Notary public key is the identity"))?.as_slice().try_into()?)
+    };
+
+    Ok(P256PrecompileSignature {
+        message_hash,
+        r: data[..32].try_into()?,
+        s: data[32..].try_into()?,
+        qx: coordinate(point.x())?,
+        qy: coordinate(point.y())?,
+    })
+}
+
+/// Convert the attestation's notary signature into `ecrecover` arguments
+pub fn ecrecover_signature(attestation: &Attestation) -> Result<EcrecoverSignature> {
+    let prehash = ecrecover_prehash(&attestation.signature.alg)?;
+    let data = &attestation.signature.data;
+
+    // r || s, with secp256k1eth signers appending their own v which we re-derive below
+    let rs = match (prehash, data.len()) {
+        (_, 64) | (Prehash::Keccak256, 65) => &data[..64],
+        (_, len) => return Err(anyhow!("Expected 64-byte {} signature, got {} bytes", attestation.signature.alg, len)),
+    };
+    let signature = K256Signature::from_slice(rs).map_err(|e| anyhow!("Invalid signature: {}", e))?;
+
+    let key = &attestation.body.verifying_key().data;
+    let verifying_key = K256VerifyingKey::from_sec1_bytes(key)
+        .map_err(|e| anyhow!("Invalid notary public key: {}", e))?;
+
+    // The notary signs the BCS-serialized header
+    let message_hash = prehash.digest(&bcs::to_bytes(&attestation.header)?);
+
+    let recovery_id = (0..2u8)
+        .filter_map(|v| RecoveryId::try_from(v).ok())
+        .find(|recovery_id| {
+            K256VerifyingKey::recover_from_prehash(&message_hash, &signature, *recovery_id)
+                .is_ok_and(|recovered| recovered == verifying_key)
+        })
+        .ok_or_else(|| anyhow!("Could not find recovery id for signature"))?;
+
+    Ok(EcrecoverSignature {
+        prehash,
+        message_hash,
+        r: rs[..32].try_into()?,
+        s: rs[32..].try_into()?,
+        // Ethereum's v is recovery_id + 27
+        v: recovery_id.to_byte() + 27,
+        address: eth::address(&verifying_key),
+    })
+}
@@ -0,0 +1,376 @@
+mod abi;
+mod config;
+mod eth;
+mod logging;
+
+use steam_zktls::types;
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use serde_json::{json, Value};
+use tracing::{info, info_span, Instrument};
+
+use abi::Token;
+use eth::{GasPrice, RpcClient, RpcError, Transaction};
+use logging::LogArgs;
+use types::SolidityProof;
+
+/// Fallback priority fee when the node has no `eth_maxPriorityFeePerGas` (1 gwei)
+const DEFAULT_PRIORITY_FEE: u128 = 1_000_000_000;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Submit an exported proof to SteamGameVerifier via JSON-RPC")]
+struct Args {
+    /// Proof JSON produced by `export`
+    #[arg(short, long, default_value = "steam_ownership.proof.json")]
+    proof: String,
+
+    /// Trade ID to resolve
+    #[arg(short, long)]
+    trade_id: u64,
+
+    /// SteamGameVerifier contract address
+    #[arg(long, env = "VERIFIER_ADDRESS")]
+    verifier: String,
+
+    /// JSON-RPC endpoint
+    #[arg(long, env = "RPC_URL", default_value = "http://localhost:8545")]
+    rpc_url: String,
+
+    /// Hex private key of the submitting account
+    #[arg(long, env = "VERIFIER_PRIVATE_KEY", conflicts_with = "keystore", hide_env_values = true)]
+    private_key: Option<String>,
+
+    /// Encrypted JSON keystore of the submitting account
+    #[arg(long)]
+    keystore: Option<String>,
+
+    /// File containing the keystore password (or set KEYSTORE_PASSWORD)
+    #[arg(long, requires = "keystore")]
+    password_file: Option<String>,
+
+    /// Call verifyAndResolve with individual arguments instead of verifyAndResolvePacked
+    #[arg(long, default_value = "false")]
+    unpacked: bool,
+
+    /// Gas limit (estimated with a 20% margin if unset)
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// Seconds to wait for the receipt
+    #[arg(long, default_value = "120")]
+    timeout: u64,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+async fn run() -> Result<()> {
+    let mut args = Args::parse();
+    args.proof = config::artifact_path(&args.proof);
+    logging::init(&args.log, "info");
+
+    let span = info_span!("submit", job_id = %args.log.job_id(), trade_id = args.trade_id);
+    submit(&args).instrument(span).await
+}
+
+async fn submit(args: &Args) -> Result<()> {
+    let key = match (&args.private_key, &args.keystore) {
+        (Some(private_key), _) => {
+            logging::redact(private_key);
+            eth::parse_private_key(private_key)?
+        }
+        (None, Some(keystore)) => {
+            let password = match &args.password_file {
+                Some(path) => tokio::fs::read_to_string(path).await?.trim_end().to_string(),
+                None => std::env::var("KEYSTORE_PASSWORD")
+                    .map_err(|_| anyhow!("Set --password-file or KEYSTORE_PASSWORD for the keystore"))?,
+            };
+            logging::redact(&password);
+            eth::load_keystore(keystore, &password)?
+        }
+        (None, None) => return Err(anyhow!("Pass --private-key (or VERIFIER_PRIVATE_KEY) or --keystore")),
+    };
+
+    let from = format!("0x{}", hex::encode(eth::address(key.verifying_key())));
+    let verifier = eth::parse_address(&args.verifier)?;
+
+    let proof: SolidityProof = serde_json::from_str(&tokio::fs::read_to_string(&args.proof).await?)?;
+    let calldata = build_calldata(&proof, args.trade_id, args.unpacked)?;
+
+    let rpc = RpcClient::new(&args.rpc_url);
+    let call = json!({
+        "from": from,
+        "to": &args.verifier,
+        "data": format!("0x{}", hex::encode(&calldata)),
+    });
+
+    // Simulate first so a revert is reported before spending gas
+    rpc.call("eth_call", json!([call, "pending"])).await?;
+
+    let chain_id = eth::quantity(&rpc.call("eth_chainId", json!([])).await?)? as u64;
+    let nonce = eth::quantity(&rpc.call("eth_getTransactionCount", json!([from, "pending"])).await?)? as u64;
+
+    let gas_limit = match args.gas_limit {
+        Some(gas_limit) => gas_limit,
+        None => {
+            let estimate = eth::quantity(&rpc.call("eth_estimateGas", json!([call])).await?)? as u64;
+            estimate + estimate / 5
+        }
+    };
+
+    let gas_price = gas_price(&rpc).await?;
+
+    info!(
+        "Submitting trade {} from {} (chain {}, nonce {}, gas {})",
+        args.trade_id, from, chain_id, nonce, gas_limit
+    );
+
+    let raw = Transaction {
+        chain_id,
+        nonce,
+        gas_limit,
+        gas_price,
+        to: verifier,
+        data: calldata,
+    }
+    .sign(&key)?;
+
+    let tx_hash = rpc
+        .call("eth_sendRawTransaction", json!([format!("0x{}", hex::encode(raw))]))
+        .await?;
+    let tx_hash = tx_hash
+        .as_str()
+        .ok_or_else(|| anyhow!("Node returned no transaction hash"))?
+        .to_string();
+
+    info!("Transaction sent: {}", tx_hash);
+
+    let receipt = wait_for_receipt(&rpc, &tx_hash, Duration::from_secs(args.timeout)).await?;
+
+    if eth::quantity(&receipt["status"])? != 1 {
+        // Replay the call at the failing block to recover the revert reason
+        let reason = match rpc.call("eth_call", json!([call, receipt["blockNumber"]])).await {
+            Err(e) => match e.downcast_ref::<RpcError>() {
+                Some(RpcError { data: Some(data), .. }) => eth::decode_revert(data),
+                _ => e.to_string(),
+            },
+            Ok(_) => "unknown reason".to_string(),
+        };
+        return Err(anyhow!("Transaction {} reverted: {}", tx_hash, reason));
+    }
+
+    println!("\n=== Submission ===");
+    println!("Transaction:      {}", tx_hash);
+    println!("Block:            {}", eth::quantity(&receipt["blockNumber"])?);
+    println!("Gas Used:         {}", eth::quantity(&receipt["gasUsed"])?);
+
+    match trade_resolved(&receipt, args.trade_id) {
+        Some((owns_game, proof_timestamp, transcript_hash)) => {
+            println!("Trade Resolved:   {}", args.trade_id);
+            println!("Buyer Owns Game:  {}", owns_game);
+            println!("Proof Timestamp:  {}", proof_timestamp);
+            println!("Transcript Hash:  0x{}", hex::encode(transcript_hash));
+        }
+        None => println!("No TradeResolved event for trade {} in receipt", args.trade_id),
+    }
+
+    Ok(())
+}
+
+/// Calldata for verifyAndResolvePacked (default) or verifyAndResolve
+fn build_calldata(proof: &SolidityProof, trade_id: u64, unpacked: bool) -> Result<Vec<u8>> {
+    // SteamGameVerifier checks the notary with ecrecover
+    let (Some(packed_proof), Some(signature_v)) = (&proof.packed_proof, proof.signature_v) else {
+        return Err(anyhow!(
+            "Proof is signed with {}, SteamGameVerifier needs a secp256k1 notary",
+            proof.binding.signature_alg
+        ));
+    };
+
+    if !unpacked {
+        let packed_proof = hex::decode(packed_proof.trim_start_matches("0x"))?;
+        return Ok(abi::encode_call(
+            "verifyAndResolvePacked(uint256,bytes)",
+            &[Token::uint(trade_id), Token::Bytes(packed_proof)],
+        ));
+    }
+
+    Ok(abi::encode_call(
+        "verifyAndResolve(uint256,bytes32,uint8,bytes32,bytes32,string,uint64,bool,bytes32)",
+        &[
+            Token::uint(trade_id),
+            Token::FixedBytes(abi::parse_bytes32(&proof.message_hash)?),
+            Token::uint(signature_v as u64),
+            Token::FixedBytes(abi::parse_bytes32(&proof.signature_r)?),
+            Token::FixedBytes(abi::parse_bytes32(&proof.signature_s)?),
+            Token::String(proof.server_name.clone()),
+            Token::uint(proof.timestamp),
+            Token::Bool(proof.owns_game),
+            Token::FixedBytes(abi::parse_bytes32(&proof.transcript_hash)?),
+        ],
+    ))
+}
+
+/// EIP-1559 fees if the chain has a base fee, legacy gas price otherwise
+async fn gas_price(rpc: &RpcClient) -> Result<GasPrice> {
+    let block = rpc.call("eth_getBlockByNumber", json!(["latest", false])).await?;
+
+    let Some(base_fee) = block.get("baseFeePerGas").filter(|v| !v.is_null()) else {
+        let gas_price = eth::quantity(&rpc.call("eth_gasPrice", json!([])).await?)?;
+        return Ok(GasPrice::Legacy { gas_price });
+    };
+
+    let base_fee = eth::quantity(base_fee)?;
+    let max_priority_fee = match rpc.call("eth_maxPriorityFeePerGas", json!([])).await {
+        Ok(fee) => eth::quantity(&fee)?,
+        Err(_) => DEFAULT_PRIORITY_FEE,
+    };
+
+    Ok(GasPrice::Eip1559 {
+        max_fee: base_fee * 2 + max_priority_fee,
+        max_priority_fee,
+    })
+}
+
+async fn wait_for_receipt(rpc: &RpcClient, tx_hash: &str, timeout: Duration) -> Result<Value> {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        let receipt = rpc.call("eth_getTransactionReceipt", json!([tx_hash])).await?;
+        if !receipt.is_null() {
+            return Ok(receipt);
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow!("Timed out waiting for receipt of {}", tx_hash));
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Decode `TradeResolved(uint256 indexed tradeId, bool, uint64, bytes32)` for our trade
+fn trade_resolved(receipt: &Value, trade_id: u64) -> Option<(bool, u64, [u8; 32])> {
+    let topic0 = format!("0x{}", hex::encode(abi::keccak256(b"TradeResolved(uint256,bool,uint64,bytes32)")));
+    let topic1 = format!("0x{:064x}", trade_id);
+
+    receipt["logs"].as_array()?.iter().find_map(|log| {
+        let topics = log["topics"].as_array()?;
+        if topics.first()?.as_str()? != topic0 || topics.get(1)?.as_str()? != topic1 {
+            return None;
+        }
+
+        let data = hex::decode(log["data"].as_str()?.trim_start_matches("0x")).ok()?;
+        if data.len() < 96 {
+            return None;
+        }
+
+        let owns_game = data[31] != 0;
+        let proof_timestamp = u64::from_be_bytes(data[56..64].try_into().ok()?);
+        let transcript_hash: [u8; 32] = data[64..96].try_into().ok()?;
+        Some((owns_game, proof_timestamp, transcript_hash))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An exported secp256k1eth proof; `packedProof` is a stand-in, it is passed through as is
+    fn proof() -> SolidityProof {
+        serde_json::from_value(json!({
+            "notaryAddress": "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+            "signatureR": format!("0x{}", "11".repeat(32)),
+            "signatureS": format!("0x{}", "22".repeat(32)),
+            "signatureV": 27,
+            "messageHash": format!("0x{}", "33".repeat(32)),
+            "messageHashAlg": "keccak256",
+            "serverName": "api.steampowered.com",
+            "timestamp": 1_700_000_000u64,
+            "ownsGame": true,
+            "transcriptHash": format!("0x{}", "44".repeat(32)),
+            "packedProof": "0xabcd",
+            "binding": {
+                "header": "0x",
+                "body": "0x",
+                "connectionInfo": "0x",
+                "signatureAlg": "secp256k1eth",
+                "signature": "0x",
+                "bodyProof": "0x",
+                "identityProof": "0x",
+                "transcriptProof": "0x",
+            },
+        }))
+        .unwrap()
+    }
+
+    fn word(value: u64) -> Vec<u8> {
+        abi::encode(&[Token::uint(value)])
+    }
+
+    #[test]
+    fn packed_calldata_wraps_the_packed_proof() {
+        let calldata = build_calldata(&proof(), 42, false).unwrap();
+
+        let mut expected = abi::selector("verifyAndResolvePacked(uint256,bytes)").to_vec();
+        expected.extend(word(42));
+        expected.extend(word(0x40));
+        expected.extend(word(2));
+        expected.extend([0xab, 0xcd]);
+        expected.resize(expected.len() + 30, 0);
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn unpacked_calldata_passes_each_field() {
+        let calldata = build_calldata(&proof(), 42, true).unwrap();
+
+        let selector =
+            abi::selector("verifyAndResolve(uint256,bytes32,uint8,bytes32,bytes32,string,uint64,bool,bytes32)");
+        assert_eq!(calldata[..4], selector);
+        let words: Vec<&[u8]> = calldata[4..].chunks(32).collect();
+        assert_eq!(words[0], word(42));
+        assert_eq!(words[1], [0x33; 32]);
+        assert_eq!(words[2], word(27));
+        assert_eq!(words[3], [0x11; 32]);
+        assert_eq!(words[4], [0x22; 32]);
+        assert_eq!(words[6], word(1_700_000_000));
+        assert_eq!(words[7], word(1));
+        assert_eq!(words[8], [0x44; 32]);
+        assert_eq!(words[10][..20], *b"api.steampowered.com");
+    }
+
+    #[test]
+    fn proofs_without_ecrecover_fields_are_rejected() {
+        let mut p256 = proof();
+        p256.packed_proof = None;
+        p256.signature_v = None;
+
+        assert!(build_calldata(&p256, 42, false).is_err());
+    }
+
+    #[test]
+    fn trade_resolved_is_read_from_our_trade_only() {
+        let data = [word(1), word(1_700_000_000), vec![0x44; 32]].concat();
+        let receipt = json!({
+            "logs": [{
+                "topics": [
+                    format!("0x{}", hex::encode(abi::keccak256(b"TradeResolved(uint256,bool,uint64,bytes32)"))),
+                    format!("0x{:064x}", 42),
+                ],
+                "data": format!("0x{}", hex::encode(data)),
+            }],
+        });
+
+        assert_eq!(trade_resolved(&receipt, 42), Some((true, 1_700_000_000, [0x44; 32])));
+        assert_eq!(trade_resolved(&receipt, 43), None);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Context, Result};
+use tls_core::{anchors::RootCertStore, key::Certificate, verify::WebPkiVerifier};
+use tlsn_core::CryptoProvider;
+
+pub use steam_zktls::verify::STEAM_API_HOST;
+
+/// Default maximum bytes the prover may send to the server
+const DEFAULT_MAX_SENT_DATA: usize = 1024;
+/// Default maximum bytes the prover may receive from the server (filtered response is small)
+const DEFAULT_MAX_RECV_DATA: usize = 4096;
+
+/// Maximum bytes the prover may send to the server (`MAX_SENT_DATA`).
+///
+/// Prover, notary and interactive verifier must agree on it.
+pub fn max_sent_data() -> usize {
+    env_limit("MAX_SENT_DATA", DEFAULT_MAX_SENT_DATA)
+}
+
+/// Maximum bytes the prover may receive from the server (`MAX_RECV_DATA`)
+pub fn max_recv_data() -> usize {
+    env_limit("MAX_RECV_DATA", DEFAULT_MAX_RECV_DATA)
+}
+
+fn env_limit(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// The TLS server the prover talks to, and which roots it trusts
+#[derive(Debug, Clone)]
+pub struct ServerTarget {
+    /// Hostname used for the TLS server name and the Host header
+    pub host: String,
+    /// Optional `host:port` to connect to instead of `host:443`
+    pub addr: Option<String>,
+    /// Optional root certificate file (PEM or DER) replacing the webpki roots
+    pub root_cert: Option<String>,
+}
+
+impl ServerTarget {
+    /// Address to open the TCP connection to
+    pub fn connect_addr(&self) -> String {
+        self.addr
+            .clone()
+            .unwrap_or_else(|| format!("{}:443", self.host))
+    }
+
+    /// Crypto provider that verifies the server certificate against our roots
+    pub fn crypto_provider(&self) -> Result<CryptoProvider> {
+        crypto_provider(self.root_cert.as_deref())
+    }
+}
+
+/// Load every certificate from a PEM or DER file
+pub fn load_root_certs(path: &str) -> Result<Vec<Vec<u8>>> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read root cert {}", path))?;
+
+    if !bytes.starts_with(b"-----BEGIN") {
+        return Ok(vec![bytes]);
+    }
+
+    let certs = rustls_pemfile::certs(&mut bytes.as_slice())
+        .map_err(|e| anyhow!("Invalid PEM in {}: {}", path, e))?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path));
+    }
+
+    Ok(certs)
+}
+
+/// Build a crypto provider, trusting only the certificates in `root_cert` if given
+pub fn crypto_provider(root_cert: Option<&str>) -> Result<CryptoProvider> {
+    let Some(path) = root_cert else {
+        return Ok(CryptoProvider::default());
+    };
+
+    let mut root_store = RootCertStore::empty();
+    for der in load_root_certs(path)? {
+        root_store
+            .add(&Certificate(der))
+            .map_err(|e| anyhow!("Invalid root certificate in {}: {:?}", path, e))?;
+    }
+
+    Ok(CryptoProvider {
+        cert: WebPkiVerifier::new(root_store, None),
+        ..Default::default()
+    })
+}
@@ -12,6 +12,26 @@ pub struct VanityUrlInner {
     pub success: i32,
 }
 
+/// Steam API response for player summaries
+#[derive(Debug, Deserialize)]
+pub struct PlayerSummariesResponse {
+    pub response: PlayerSummariesInner,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerSummariesInner {
+    #[serde(default)]
+    pub players: Vec<PlayerSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerSummary {
+    pub steamid: String,
+    /// 1 = private, 3 = public
+    pub communityvisibilitystate: u32,
+    pub personaname: Option<String>,
+}
+
 /// Steam API response for owned games
 #[derive(Debug, Deserialize)]
 pub struct OwnedGamesResponse {
@@ -42,6 +62,16 @@ pub struct SteamOwnershipClaim {
     pub app_id: u32,
     /// Whether ownership was verified
     pub owns_game: bool,
+    /// Notaries of a multi-notary run, in order; notary `i`'s attestation, secrets and claim
+    /// are under `<prefix>.<i>`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notaries: Vec<String>,
+    /// Notary that signed the attestation: `host:port`, or `embedded`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notary: Option<String>,
+    /// That notary's public key (hex), as in the attestation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notary_key: Option<String>,
 }
 
 /// Proof output that contains the TLSNotary attestation
@@ -56,3 +86,145 @@ pub struct OwnershipProof {
     /// Timestamp of proof generation
     pub timestamp: u64,
 }
+
+/// Verification result matching Solidity's VerificationResult struct
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationResult {
+    /// Whether the user owns the game
+    pub owns_game: bool,
+    /// Unix timestamp of the TLS connection
+    pub timestamp: u64,
+    /// SHA256 hash of the revealed transcript data
+    pub transcript_hash: String,
+}
+
+/// Solidity-compatible proof structure
+///
+/// secp256k1eth notaries get the `ecrecover` fields and packed calldata; secp256r1 notaries get
+/// `p256` for the RIP-7212 precompile instead. SHA-256 prehashed secp256k1 notaries are not
+/// exported, since no contract recomputes their `messageHash`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolidityProof {
+    /// Notary's Ethereum address (derived from secp256k1 public key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notary_address: Option<String>,
+    /// Signature r value (32 bytes)
+    pub signature_r: String,
+    /// Signature s value (32 bytes)
+    pub signature_s: String,
+    /// Signature v value (27 or 28), secp256k1 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_v: Option<u8>,
+    /// Hash of the attestation header that the notary signed
+    pub message_hash: String,
+    /// How `message_hash` was computed: `sha256` or `keccak256` (Ethereum-style notary)
+    pub message_hash_alg: String,
+    /// Server name from the proof
+    pub server_name: String,
+    /// Unix timestamp of the TLS connection
+    pub timestamp: u64,
+    /// Whether user owns the game (game_count >= 1 from Steam API)
+    pub owns_game: bool,
+    /// Hash of the revealed transcript data
+    pub transcript_hash: String,
+    /// abi.encode(bytes32,uint8,bytes32,bytes32,string,uint64,bool,bytes32) of the fields above
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packed_proof: Option<String>,
+    /// Ready-to-send calldata for the packed entry points
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<ProofCalldata>,
+    /// Notary public key and precompile input, secp256r1 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p256: Option<P256Proof>,
+    /// Signed data the fields above can be re-derived from
+    pub binding: SignatureBinding,
+}
+
+/// secp256r1 notary signature in the RIP-7212 `P256VERIFY` precompile layout
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct P256Proof {
+    /// Notary public key x coordinate (32 bytes)
+    pub qx: String,
+    /// Notary public key y coordinate (32 bytes)
+    pub qy: String,
+    /// `messageHash || r || s || qx || qy` (160 bytes), the precompile's input
+    pub precompile_input: String,
+}
+
+/// Full calldata (selector + arguments) for contracts that take the packed proof
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofCalldata {
+    /// `SteamOwnershipVerifier.verifyOwnershipPacked(proof)`
+    pub verify_ownership_packed: String,
+    /// `SteamGameGate.claimRewardPacked(proof)`
+    pub claim_reward_packed: String,
+    /// `SteamGameVerifier.verifyAndResolvePacked(tradeId, proof)`, only with `--trade-id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_and_resolve_packed: Option<String>,
+}
+
+/// Everything needed to bind the proof fields to the notary signature.
+///
+/// All byte fields are 0x-prefixed hex of BCS, the encoding TLSNotary signs and hashes.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureBinding {
+    /// Attestation header; `messageHash` is its hash under `messageHashAlg`
+    pub header: String,
+    /// Attestation body; its Merkle root is the header's `root`
+    pub body: String,
+    /// Connection info from the body (time, TLS version, transcript length)
+    pub connection_info: String,
+    /// Notary signature algorithm
+    pub signature_alg: String,
+    /// Raw notary signature
+    pub signature: String,
+    /// Merkle proof of the body fields against the header root
+    pub body_proof: String,
+    /// Server identity opening (certificate chain and handshake) behind `serverName`
+    pub identity_proof: String,
+    /// Openings of the revealed transcript ranges against the body's commitments
+    pub transcript_proof: String,
+}
+
+/// Solidity proofs of one claim from several notaries, for a k-of-n on-chain check
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiNotaryProof {
+    /// Distinct notaries that must attest to the claim
+    pub threshold: usize,
+    /// What every proof below attests to
+    pub owns_game: bool,
+    /// One proof per distinct trusted notary, in bundle order
+    pub proofs: Vec<SolidityProof>,
+}
+
+/// EIP-712 signed statement that a verifier checked a proof for a trade
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationReceipt {
+    pub trade_id: u64,
+    pub app_id: u32,
+    pub owns_game: bool,
+    /// Unix timestamp of the TLS connection
+    pub timestamp: u64,
+    /// SHA256 hash of the revealed transcript data
+    pub transcript_hash: String,
+    /// Ethereum address of the notary that signed the attestation
+    pub notary: String,
+    /// EIP-712 domain chain id
+    pub chain_id: u64,
+    /// EIP-712 domain verifying contract, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifying_contract: Option<String>,
+    /// EIP-712 digest that was signed
+    pub digest: String,
+    /// Address of the receipt key
+    pub signer: String,
+    /// 65-byte r || s || v signature, ready for `ecrecover(digest, v, r, s)`
+    pub signature: String,
+}
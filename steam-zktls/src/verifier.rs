@@ -1,23 +1,45 @@
-mod types;
+mod abi;
+mod config;
+mod encoding;
+mod eth;
+mod logging;
+mod metrics;
+mod policy;
+mod receipt;
+mod signature;
+mod tls;
+
+use steam_zktls::{
+    envelope, types,
+    verify::{verify_bundle, verify_presentation, VerifiedPresentation},
+};
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use clap::Parser;
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::process::ExitCode;
-use tlsn_core::{
-    presentation::{Presentation, PresentationOutput},
-    CryptoProvider,
-};
+use tlsn_core::{presentation::Presentation, CryptoProvider};
+use tracing::{error, info, info_span, warn, Instrument};
+
+use envelope::ArtifactKind;
+use logging::LogArgs;
+use policy::{Policy, PolicyArgs};
+use receipt::ReceiptDomain;
+use signature::OnChainSignature;
+use tls::STEAM_API_HOST;
+use types::VerificationResult;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Verify Steam game ownership proof")]
 struct Args {
-    /// Path to the presentation file
+    /// Presentation: binary, or a base64/hex JSON wrapper (`-` for stdin)
     #[arg(short, long, default_value = "steam_ownership.presentation.tlsn")]
     presentation: String,
 
+    /// The presentation is a multi-notary bundle; see --threshold
+    #[arg(long, default_value = "false")]
+    bundle: bool,
+
     /// App ID to verify
     #[arg(short, long)]
     app_id: u32,
@@ -29,25 +51,82 @@ struct Args {
     /// Output as JSON (matches Solidity VerificationResult struct)
     #[arg(short, long, default_value = "false")]
     json: bool,
+
+    /// Server name the proof must have been generated against
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    expected_server: String,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    /// Hex private key to sign an EIP-712 verification receipt with
+    #[arg(long, env = "RECEIPT_SIGNING_KEY", hide_env_values = true, requires = "trade_id")]
+    receipt_key: Option<String>,
+
+    /// Trade ID the receipt is for
+    #[arg(short, long)]
+    trade_id: Option<u64>,
+
+    /// EIP-712 domain chain id for the receipt
+    #[arg(long, env = "CHAIN_ID", default_value = "31337")]
+    chain_id: u64,
+
+    /// EIP-712 domain verifying contract for the receipt (omitted from the domain if unset)
+    #[arg(long, env = "RECEIPT_VERIFYING_CONTRACT")]
+    verifying_contract: Option<String>,
+
+    /// Where to write the signed receipt
+    #[arg(long, default_value = "steam_ownership.receipt.json")]
+    receipt_out: String,
+
+    /// Write phase durations and the outcome to this file as JSON
+    #[arg(long)]
+    metrics_json: Option<String>,
+
+    #[command(flatten)]
+    policy: PolicyArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
-/// Verification result matching Solidity's VerificationResult struct
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct VerificationResult {
-    /// Whether the user owns the game
-    owns_game: bool,
-    /// Unix timestamp of the TLS connection
-    timestamp: u64,
-    /// SHA256 hash of the revealed transcript data
-    transcript_hash: String,
+fn main() -> ExitCode {
+    let runtime = config::load().and_then(|()| Ok(tokio::runtime::Runtime::new()?));
+    match runtime {
+        Ok(runtime) => runtime.block_on(run()),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
 }
 
-#[tokio::main]
-async fn main() -> ExitCode {
-    let args = Args::parse();
+async fn run() -> ExitCode {
+    let mut args = Args::parse();
+    args.presentation = config::artifact_path(&args.presentation);
+    args.receipt_out = config::artifact_path(&args.receipt_out);
+    // Details only with --verbose; stdout is reserved for the result
+    logging::init(&args.log, if args.verbose { "info" } else { "warn" });
+    if let Some(receipt_key) = &args.receipt_key {
+        logging::redact(receipt_key);
+    }
 
-    match verify(&args).await {
+    let span = info_span!(
+        "verify",
+        job_id = %args.log.job_id(),
+        trade_id = args.trade_id,
+        app_id = args.app_id
+    );
+    let outcome = verify(&args).instrument(span).await;
+    metrics::global().record_outcome("verify", &outcome);
+    if let Some(path) = &args.metrics_json {
+        if let Err(e) = metrics::write_json(path) {
+            warn!("Failed to write metrics: {:#}", e);
+        }
+    }
+
+    match outcome {
         Ok(result) => {
             if args.json {
                 println!("{}", serde_json::to_string_pretty(&result).unwrap());
@@ -62,10 +141,10 @@ async fn main() -> ExitCode {
         }
         Err(e) => {
             if args.verbose {
-                eprintln!("error: {}", e);
+                error!("{:#}", e);
             }
             if args.json {
-                eprintln!("{{\"error\": \"{}\"}}", e);
+                eprintln!("{{\"error\": \"{:#}\"}}", e);
             } else {
                 println!("no");
             }
@@ -75,76 +154,118 @@ async fn main() -> ExitCode {
 }
 
 async fn verify(args: &Args) -> Result<VerificationResult> {
-    // Load the presentation
-    let presentation: Presentation = bincode::deserialize(
-        &tokio::fs::read(&args.presentation).await?
-    )?;
+    let provider = tls::crypto_provider(args.root_cert.as_deref())?;
+    let policy = args.policy.load()?;
 
-    let provider = CryptoProvider::default();
+    // Verify the presentation (or every member of the bundle), check the server and policy,
+    // and extract the result
+    let VerifiedPresentation {
+        result,
+        server_name,
+        attestation,
+    } = if args.bundle {
+        verify_bundle_file(args, &provider, &policy).await?
+    } else {
+        verify_presentation_file(args, &provider, &policy).await?
+    };
+    metrics::global().record_verification(&server_name, Some(args.app_id), result.owns_game);
 
-    if args.verbose {
-        let verifying_key = presentation.verifying_key();
-        eprintln!(
-            "Verifying with {} key: {}",
-            verifying_key.alg,
-            hex::encode(&verifying_key.data)
-        );
+    // Valid off-chain either way, but only some notary algorithms can be checked by the contracts
+    match signature::on_chain_signature(&attestation) {
+        Ok(OnChainSignature::Ecrecover(signature)) => {
+            info!("signature: {} ({} prehash, ecrecover-compatible)", attestation.signature.alg, signature.prehash);
+            info!("notary_address: 0x{}", hex::encode(signature.address));
+        }
+        Ok(OnChainSignature::P256(signature)) => {
+            info!("signature: {} (sha256 prehash, P256VERIFY-compatible)", attestation.signature.alg);
+            info!("notary_key: (0x{}, 0x{})", hex::encode(signature.qx), hex::encode(signature.qy));
+        }
+        Err(e) => warn!("{}", e),
     }
 
-    // Verify the presentation cryptographically
-    let PresentationOutput {
-        server_name,
-        connection_info,
-        transcript,
-        ..
-    } = presentation.verify(&provider)?;
-
-    // Extract timestamp
-    let timestamp = connection_info.time;
-    let connection_time = DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+    let connection_time = DateTime::<Utc>::from_timestamp(result.timestamp as i64, 0)
         .ok_or_else(|| anyhow!("Invalid timestamp"))?;
 
-    let server_name = server_name.ok_or_else(|| anyhow!("No server name in proof"))?;
+    info!("server: {}", server_name);
+    info!("timestamp: {} ({})", result.timestamp, connection_time.format("%Y-%m-%d %H:%M:%S UTC"));
+    info!("owns_game: {}", result.owns_game);
+    info!("transcript_hash: {}", result.transcript_hash);
 
-    // Verify it's from Steam API
-    if server_name.as_str() != "api.steampowered.com" {
-        if args.verbose {
-            eprintln!("Invalid server: {}", server_name.as_str());
-        }
-        return Err(anyhow!("Invalid server: {}", server_name.as_str()));
+    if let Some(receipt_key) = &args.receipt_key {
+        // Receipts name the notary by address, so they need a secp256k1 notary of either prehash;
+        // the receipt digest, not the header hash, is what gets checked on-chain
+        let Ok(signature) = signature::ecrecover_signature(&attestation) else {
+            return Err(anyhow!(
+                "Receipts need a secp256k1 notary, attestation is signed with {}",
+                attestation.signature.alg
+            ));
+        };
+
+        let domain = ReceiptDomain {
+            chain_id: args.chain_id,
+            verifying_contract: args.verifying_contract.as_deref().map(eth::parse_address).transpose()?,
+        };
+        let receipt = receipt::sign(
+            &eth::parse_private_key(receipt_key)?,
+            &domain,
+            args.trade_id.unwrap_or_default(),
+            args.app_id,
+            &result,
+            signature.address,
+        )?;
+
+        tokio::fs::write(&args.receipt_out, serde_json::to_string_pretty(&receipt)?).await?;
+
+        info!("receipt: {} signed by {} ({})", args.receipt_out, receipt.signer, receipt.digest);
     }
 
-    // Get transcript data
-    let mut partial_transcript = transcript.ok_or_else(|| anyhow!("No transcript in proof"))?;
-    partial_transcript.set_unauthed(b'X');
+    Ok(result)
+}
 
-    let transcript_bytes = partial_transcript.received_unsafe();
-    let recv = String::from_utf8_lossy(transcript_bytes);
+async fn verify_presentation_file(
+    args: &Args,
+    provider: &CryptoProvider,
+    policy: &Policy,
+) -> Result<VerifiedPresentation> {
+    let presentation: Presentation = metrics::timed(
+        "decode",
+        encoding::read(ArtifactKind::Presentation, &args.presentation),
+    )
+    .await?;
 
-    // Compute transcript hash (matches Solidity)
-    let mut hasher = Sha256::new();
-    hasher.update(transcript_bytes);
-    let transcript_hash: [u8; 32] = hasher.finalize().into();
+    let verifying_key = presentation.verifying_key();
+    info!(
+        "Verifying with {} key: {}",
+        verifying_key.alg,
+        hex::encode(&verifying_key.data)
+    );
 
-    // Check for game_count in revealed data
-    let owns_game = recv.contains("\"game_count\":1");
-    let doesnt_own = recv.contains("\"game_count\":0");
+    let verified = metrics::timed_sync("verify", || {
+        verify_presentation(presentation, provider, &args.expected_server, args.app_id)
+    })?;
+    metrics::timed_sync("policy", || {
+        policy.check(verified.attestation.body.verifying_key(), verified.result.timestamp)
+    })?;
 
-    if args.verbose {
-        eprintln!("server: {}", server_name.as_str());
-        eprintln!("timestamp: {} ({})", timestamp, connection_time.format("%Y-%m-%d %H:%M:%S UTC"));
-        eprintln!("app_id: {}", args.app_id);
-        eprintln!("owns_game: {}", owns_game);
-        eprintln!("transcript_hash: 0x{}", hex::encode(&transcript_hash));
-    }
+    Ok(verified)
+}
 
-    if !owns_game && !doesnt_own {
-        return Err(anyhow!("Invalid proof - no game_count revealed"));
+async fn verify_bundle_file(
+    args: &Args,
+    provider: &CryptoProvider,
+    policy: &Policy,
+) -> Result<VerifiedPresentation> {
+    let presentations: Vec<Presentation> =
+        metrics::timed("decode", encoding::read(ArtifactKind::Bundle, &args.presentation)).await?;
+
+    info!("Verifying a bundle of {} presentations", presentations.len());
+    for presentation in &presentations {
+        let verifying_key = presentation.verifying_key();
+        info!("  {} key: {}", verifying_key.alg, hex::encode(&verifying_key.data));
     }
 
-    Ok(VerificationResult {
-        owns_game,
-        timestamp,
-        transcript_hash: format!("0x{}", hex::encode(&transcript_hash)),
-    })
+    let members = metrics::timed_sync("verify", || {
+        verify_bundle(presentations, provider, &args.expected_server, args.app_id)
+    })?;
+    metrics::timed_sync("policy", || policy.accept_bundle(members))
 }
@@ -0,0 +1,92 @@
+use anyhow::{anyhow, Result};
+use tlsn_core::{
+    attestation::Attestation,
+    presentation::{Presentation, PresentationOutput},
+    CryptoProvider,
+};
+
+use crate::{
+    envelope::{self, ArtifactKind},
+    ownership,
+    types::VerificationResult,
+};
+
+/// Default Steam Web API host, and the server name proofs are expected to come from
+pub const STEAM_API_HOST: &str = "api.steampowered.com";
+
+/// A verified presentation and what it proves
+#[derive(Debug)]
+pub struct VerifiedPresentation {
+    pub result: VerificationResult,
+    pub server_name: String,
+    /// The notarized attestation, for signature and notary checks
+    pub attestation: Attestation,
+}
+
+/// Verify a presentation cryptographically, check the server and extract the result for `app_id`
+pub fn verify_presentation(
+    presentation: Presentation,
+    provider: &CryptoProvider,
+    expected_server: &str,
+    app_id: u32,
+) -> Result<VerifiedPresentation> {
+    let PresentationOutput {
+        server_name,
+        connection_info,
+        transcript,
+        attestation,
+        ..
+    } = presentation.verify(provider)?;
+
+    let server_name = server_name.ok_or_else(|| anyhow!("No server name in proof"))?;
+    let transcript = transcript.ok_or_else(|| anyhow!("No transcript in proof"))?;
+
+    let result = ownership::verification_result(
+        server_name.as_str(),
+        expected_server,
+        connection_info.time,
+        transcript,
+        Some(app_id),
+    )?;
+
+    Ok(VerifiedPresentation {
+        result,
+        server_name: server_name.as_str().to_string(),
+        attestation,
+    })
+}
+
+/// [`verify_presentation`] on an enveloped (or legacy raw) presentation file's bytes
+pub fn verify_bytes(
+    bytes: &[u8],
+    provider: &CryptoProvider,
+    expected_server: &str,
+    app_id: u32,
+) -> Result<VerifiedPresentation> {
+    let presentation: Presentation = envelope::unwrap(ArtifactKind::Presentation, bytes)?;
+    verify_presentation(presentation, provider, expected_server, app_id)
+}
+
+/// [`verify_presentation`] on every member of a multi-notary bundle.
+///
+/// Fails if any member does not verify; whether enough distinct, trusted notaries agree is up
+/// to the caller's policy.
+pub fn verify_bundle(
+    presentations: Vec<Presentation>,
+    provider: &CryptoProvider,
+    expected_server: &str,
+    app_id: u32,
+) -> Result<Vec<VerifiedPresentation>> {
+    if presentations.is_empty() {
+        return Err(anyhow!("Empty bundle"));
+    }
+
+    presentations
+        .into_iter()
+        .enumerate()
+        .map(|(i, presentation)| {
+            verify_presentation(presentation, provider, expected_server, app_id)
+                .map_err(|e| anyhow!("Bundle member {}: {}", i, e))
+        })
+        .collect()
+}
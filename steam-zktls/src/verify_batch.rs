@@ -0,0 +1,271 @@
+mod config;
+mod encoding;
+mod logging;
+mod metrics;
+mod policy;
+mod tls;
+
+use steam_zktls::{envelope, types, verify};
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::json;
+use tlsn_core::{presentation::Presentation, CryptoProvider};
+use tracing::{info, info_span};
+
+use envelope::ArtifactKind;
+use logging::LogArgs;
+use policy::{Policy, PolicyArgs};
+use tls::STEAM_API_HOST;
+use types::VerificationResult;
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Verify many presentations in parallel and stream one JSON result per line"
+)]
+struct Args {
+    /// Directory of `*.presentation.tlsn`/`.json` (or `*.bundle.tlsn`) files, a glob, or a `.jsonl` manifest
+    input: String,
+
+    /// App ID for directory and glob inputs (manifest entries carry their own)
+    #[arg(short, long)]
+    app_id: Option<u32>,
+
+    /// Default server name proofs must have been generated against
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    expected_server: String,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    /// Worker threads (defaults to the number of CPU cores)
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Write phase durations, outcomes and results per server and app to this file as JSON
+    #[arg(long)]
+    metrics_json: Option<String>,
+
+    #[command(flatten)]
+    policy: PolicyArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// One presentation to verify and the context it must match
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    presentation: PathBuf,
+    app_id: u32,
+    #[serde(default)]
+    trade_id: Option<u64>,
+    /// Overrides `--expected-server`
+    #[serde(default)]
+    expected_server: Option<String>,
+    /// Earliest acceptable connection time (unix seconds)
+    #[serde(default)]
+    not_before: Option<u64>,
+    /// Latest acceptable connection time (unix seconds)
+    #[serde(default)]
+    not_after: Option<u64>,
+}
+
+fn main() -> Result<ExitCode> {
+    config::load()?;
+    let mut args = Args::parse();
+    args.input = config::artifact_path(&args.input);
+    // Logs go to stderr; stdout carries the result lines
+    logging::init(&args.log, "info");
+
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global()?;
+    }
+
+    // Read the root cert once; the provider is shared by all workers
+    let provider = tls::crypto_provider(args.root_cert.as_deref())?;
+    let policy = args.policy.load()?;
+
+    let entries = load_entries(&args)?;
+    let batch_span = info_span!("verify_batch", job_id = %args.log.job_id());
+    info!(parent: &batch_span, "Verifying {} presentations", entries.len());
+
+    let started = Instant::now();
+    let owned = AtomicUsize::new(0);
+    let not_owned = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+
+    // Lines are printed as each presentation finishes, so output order is not input order
+    entries.par_iter().for_each(|entry| {
+        let span = info_span!(
+            parent: &batch_span,
+            "entry",
+            presentation = %entry.presentation.display(),
+            trade_id = entry.trade_id,
+            app_id = entry.app_id
+        );
+        let outcome = span.in_scope(|| verify_entry(entry, &args, &provider, &policy));
+        metrics::global().record_outcome("verify", &outcome);
+
+        let line = match outcome {
+            Ok(result) => {
+                let counter = if result.owns_game { &owned } else { &not_owned };
+                counter.fetch_add(1, Ordering::Relaxed);
+                json!({
+                    "presentation": entry.presentation,
+                    "appId": entry.app_id,
+                    "tradeId": entry.trade_id,
+                    "ownsGame": result.owns_game,
+                    "timestamp": result.timestamp,
+                    "transcriptHash": result.transcript_hash,
+                })
+            }
+            Err(e) => {
+                failed.fetch_add(1, Ordering::Relaxed);
+                json!({
+                    "presentation": entry.presentation,
+                    "appId": entry.app_id,
+                    "tradeId": entry.trade_id,
+                    "error": format!("{:#}", e),
+                })
+            }
+        };
+        println!("{}", line);
+    });
+
+    let failed = failed.into_inner();
+    println!(
+        "{}",
+        json!({
+            "summary": {
+                "total": entries.len(),
+                "owned": owned.into_inner(),
+                "notOwned": not_owned.into_inner(),
+                "failed": failed,
+                "elapsedMs": started.elapsed().as_millis() as u64,
+            }
+        })
+    );
+
+    if let Some(path) = &args.metrics_json {
+        metrics::write_json(path)?;
+    }
+
+    Ok(if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn verify_entry(
+    entry: &Entry,
+    args: &Args,
+    provider: &CryptoProvider,
+    policy: &Policy,
+) -> Result<VerificationResult> {
+    let bytes = std::fs::read(&entry.presentation)
+        .with_context(|| format!("Failed to read {}", entry.presentation.display()))?;
+
+    // Multi-notary bundles are told apart by name
+    let kind = ArtifactKind::from_path(&entry.presentation.to_string_lossy())
+        .filter(|kind| *kind == ArtifactKind::Bundle)
+        .unwrap_or(ArtifactKind::Presentation);
+    let bytes = metrics::timed_sync("decode", || encoding::decode(kind, &bytes))?;
+
+    let expected_server = entry.expected_server.as_deref().unwrap_or(&args.expected_server);
+    let verified = if kind == ArtifactKind::Bundle {
+        let presentations: Vec<Presentation> = envelope::unwrap(kind, &bytes)?;
+        let members = metrics::timed_sync("verify", || {
+            verify::verify_bundle(presentations, provider, expected_server, entry.app_id)
+        })?;
+        metrics::timed_sync("policy", || policy.accept_bundle(members))?
+    } else {
+        let verified = metrics::timed_sync("verify", || {
+            verify::verify_bytes(&bytes, provider, expected_server, entry.app_id)
+        })?;
+        metrics::timed_sync("policy", || {
+            policy.check(verified.attestation.body.verifying_key(), verified.result.timestamp)
+        })?;
+        verified
+    };
+
+    let timestamp = verified.result.timestamp;
+    if entry.not_before.is_some_and(|t| timestamp < t) || entry.not_after.is_some_and(|t| timestamp > t) {
+        return Err(anyhow!("Connection time {} is outside the expected window", timestamp)
+            .context(metrics::PhaseFailed("time_window")));
+    }
+
+    metrics::global().record_verification(&verified.server_name, Some(entry.app_id), verified.result.owns_game);
+
+    Ok(verified.result)
+}
+
+/// Expand the input into entries: a manifest, a directory or a glob
+fn load_entries(args: &Args) -> Result<Vec<Entry>> {
+    let input = Path::new(&args.input);
+
+    if input.is_file() && input.extension().is_some_and(|e| e == "jsonl") {
+        return load_manifest(input);
+    }
+
+    let app_id = args
+        .app_id
+        .ok_or_else(|| anyhow!("--app-id is required for directory and glob inputs"))?;
+
+    let paths: Vec<PathBuf> = if input.is_dir() {
+        std::fs::read_dir(input)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let name = p.to_string_lossy();
+                name.ends_with(".presentation.tlsn")
+                    || name.ends_with(".presentation.json")
+                    || name.ends_with(".bundle.tlsn")
+            })
+            .collect()
+    } else {
+        glob::glob(&args.input)?.collect::<Result<_, _>>()?
+    };
+
+    if paths.is_empty() {
+        return Err(anyhow!("No presentations found in {}", args.input));
+    }
+
+    Ok(paths
+        .into_iter()
+        .map(|presentation| Entry {
+            presentation,
+            app_id,
+            trade_id: None,
+            expected_server: None,
+            not_before: None,
+            not_after: None,
+        })
+        .collect())
+}
+
+/// One JSON entry per line; relative paths are resolved against the manifest's directory
+fn load_manifest(path: &Path) -> Result<Vec<Entry>> {
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    std::fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            let mut entry: Entry = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid manifest entry", path.display(), n + 1))?;
+            entry.presentation = base.join(&entry.presentation);
+            Ok(entry)
+        })
+        .collect()
+}
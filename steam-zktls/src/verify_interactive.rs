@@ -0,0 +1,227 @@
+mod config;
+mod logging;
+mod metrics;
+mod tls;
+
+use steam_zktls::{ownership, types};
+
+use std::convert::Infallible;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use http_body_util::Full;
+use hyper::{body::Bytes, service::service_fn, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::json;
+use tlsn_common::config::ProtocolConfigValidator;
+use tlsn_verifier::{SessionInfo, Verifier, VerifierConfig};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::{info, info_span, warn, Instrument};
+
+use tls::STEAM_API_HOST;
+use types::VerificationResult;
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Act as the MPC-TLS verifier for provers and print a VerificationResult per session"
+)]
+struct Args {
+    /// Address to listen on for provers
+    #[arg(short, long, default_value = "127.0.0.1:7048")]
+    listen: String,
+
+    /// App ID every session must prove ownership (or non-ownership) of
+    #[arg(short, long)]
+    app_id: u32,
+
+    /// Trade ID the sessions settle, echoed in each result line
+    #[arg(short, long)]
+    trade_id: Option<u64>,
+
+    /// Server name the prover must have connected to
+    #[arg(long, env = "STEAM_API_HOST", default_value = STEAM_API_HOST)]
+    expected_server: String,
+
+    /// Root certificate (PEM or DER) to trust instead of the webpki roots
+    #[arg(long, env = "STEAM_API_ROOT_CERT")]
+    root_cert: Option<String>,
+
+    /// Serve Prometheus metrics on `/metrics` (and JSON on `/metrics.json`) at this address
+    #[arg(long, env = "METRICS_LISTEN")]
+    metrics_listen: Option<String>,
+
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+fn main() -> Result<()> {
+    config::load()?;
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, "info");
+    let job_id = args.log.job_id();
+
+    // Fail early on a bad root cert rather than on the first session
+    tls::crypto_provider(args.root_cert.as_deref())?;
+
+    if let Some(addr) = &args.metrics_listen {
+        let metrics_listener = TcpListener::bind(addr).await?;
+        info!("Metrics on http://{}/metrics", addr);
+        tokio::spawn(serve_metrics(metrics_listener));
+    }
+
+    let listener = TcpListener::bind(&args.listen).await?;
+    info!("Interactive verifier listening on {}", args.listen);
+
+    let mut session = 0u64;
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        session += 1;
+        let span = info_span!(
+            "session",
+            job_id = %job_id,
+            session,
+            %peer,
+            trade_id = args.trade_id,
+            app_id = args.app_id
+        );
+        info!(parent: &span, "Prover connected");
+
+        let expected_server = args.expected_server.clone();
+        let root_cert = args.root_cert.clone();
+        let (app_id, trade_id) = (args.app_id, args.trade_id);
+
+        let session_task = async move {
+            // One JSON line per session on stdout, result or error, bound to the app and trade
+            let outcome = verify_session(socket, &expected_server, root_cert.as_deref(), app_id).await;
+            metrics::global().record_outcome("verify", &outcome);
+
+            let line = match outcome {
+                Ok(result) => {
+                    info!("Session verified: owns_game={}", result.owns_game);
+                    json!({
+                        "appId": app_id,
+                        "tradeId": trade_id,
+                        "ownsGame": result.owns_game,
+                        "timestamp": result.timestamp,
+                        "transcriptHash": result.transcript_hash,
+                    })
+                }
+                Err(e) => {
+                    warn!("Session failed: {:#}", e);
+                    json!({
+                        "appId": app_id,
+                        "tradeId": trade_id,
+                        "error": format!("{:#}", e),
+                    })
+                }
+            };
+            println!("{}", line);
+        };
+        tokio::spawn(session_task.instrument(span));
+    }
+}
+
+/// Run the verifier side of MPC-TLS with one prover and check what it revealed is for `app_id`
+async fn verify_session(
+    socket: TcpStream,
+    expected_server: &str,
+    root_cert: Option<&str>,
+    app_id: u32,
+) -> Result<VerificationResult> {
+    let config_validator = ProtocolConfigValidator::builder()
+        .max_sent_data(tls::max_sent_data())
+        .max_recv_data(tls::max_recv_data())
+        .build()?;
+
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .protocol_config_validator(config_validator)
+            .crypto_provider(tls::crypto_provider(root_cert)?)
+            .build()?,
+    );
+
+    // Verify MPC-TLS and wait for the (redacted) transcript
+    let (transcript, SessionInfo { server_name, connection_info, .. }) =
+        metrics::timed("mpc_tls", async { anyhow::Ok(verifier.verify(socket.compat()).await?) }).await?;
+
+    metrics::global().add_bytes(
+        connection_info.transcript_length.sent as usize,
+        connection_info.transcript_length.received as usize,
+    );
+
+    let timestamp = connection_info.time;
+    if let Some(connection_time) = DateTime::<Utc>::from_timestamp(timestamp as i64, 0) {
+        info!(
+            "Session with {} at {}",
+            server_name.as_str(),
+            connection_time.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+    }
+
+    let result = metrics::timed_sync("ownership", || {
+        ownership::verification_result(
+            server_name.as_str(),
+            expected_server,
+            timestamp,
+            transcript,
+            Some(app_id),
+        )
+    })?;
+    metrics::global().record_verification(server_name.as_str(), Some(app_id), result.owns_game);
+
+    Ok(result)
+}
+
+/// Serve the process metrics over plain HTTP until the listener fails
+async fn serve_metrics(listener: TcpListener) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Metrics listener failed: {}", e);
+                return;
+            }
+        };
+
+        tokio::spawn(async move {
+            let service = service_fn(|req| async move { Ok::<_, Infallible>(metrics_response(&req)) });
+
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Metrics connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn metrics_response<B>(req: &Request<B>) -> Response<Full<Bytes>> {
+    let (status, content_type, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => (
+            StatusCode::OK,
+            "text/plain; version=0.0.4",
+            metrics::global().render_prometheus(),
+        ),
+        (&Method::GET, "/metrics.json") => (
+            StatusCode::OK,
+            "application/json",
+            metrics::global().to_json().to_string(),
+        ),
+        _ => (StatusCode::NOT_FOUND, "text/plain", "not found\n".to_string()),
+    };
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .body(Full::new(Bytes::from(body)))
+        .expect("static response parts are valid")
+}
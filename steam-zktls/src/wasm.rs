@@ -0,0 +1,14 @@
+use tlsn_core::CryptoProvider;
+use wasm_bindgen::prelude::*;
+
+use crate::verify::{self, STEAM_API_HOST};
+
+/// Verify a presentation (as written by `present`) against the webpki roots and return the
+/// `VerificationResult` as a JS object: `{ ownsGame, timestamp, transcriptHash }`
+#[wasm_bindgen]
+pub fn verify(bytes: &[u8], app_id: u32) -> Result<JsValue, JsError> {
+    let verified = verify::verify_bytes(bytes, &CryptoProvider::default(), STEAM_API_HOST, app_id)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    Ok(serde_wasm_bindgen::to_value(&verified.result)?)
+}
@@ -0,0 +1,73 @@
+//! A `fake-steam` process for the integration tests
+
+// Each test crate uses its own subset
+#![allow(dead_code)]
+
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Hostname the fake server's certificate is issued for
+pub const FAKE_HOST: &str = "localhost";
+
+/// In the built-in fixture: owns 730 and 620
+pub const OWNER_STEAM_ID: &str = "76561198000000001";
+/// In the built-in fixture: private profile
+pub const PRIVATE_STEAM_ID: &str = "76561198000000002";
+
+/// Any key of the real length; the built-in fixture accepts every key
+pub const API_KEY: &str = "0123456789ABCDEF0123456789ABCDEF";
+
+/// A running `fake-steam`, killed on drop
+pub struct FakeSteam {
+    child: Child,
+    pub addr: SocketAddr,
+    /// Root certificate (PEM) the server's certificate chains to
+    pub ca_path: PathBuf,
+}
+
+impl FakeSteam {
+    /// Start the server with the built-in fixture on a free port and wait until it accepts
+    pub fn start() -> Self {
+        // Reserve a free port; the server binds it right after
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("free port");
+        let ca_path = std::env::temp_dir().join(format!("fake_steam_{}_{}.ca.pem", std::process::id(), addr.port()));
+
+        let child = Command::new(env!("CARGO_BIN_EXE_fake-steam"))
+            .args(["--listen", &addr.to_string(), "--host", FAKE_HOST, "--ca-out"])
+            .arg(&ca_path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn fake-steam");
+        let server = FakeSteam { child, addr, ca_path };
+
+        // The root certificate is written before the listener is bound
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(addr).is_err() {
+            assert!(Instant::now() < deadline, "fake-steam did not start listening on {}", addr);
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        server
+    }
+
+    /// Root certificate as PEM
+    pub fn ca_pem(&self) -> Vec<u8> {
+        std::fs::read(&self.ca_path).expect("read fake-steam root certificate")
+    }
+}
+
+impl Drop for FakeSteam {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.ca_path);
+    }
+}
@@ -0,0 +1,191 @@
+//! Notarize the owned-games query against `fake-steam` with the embedded notary, present it
+//! with the disclosure `present` uses, then verify the presentation the way `verifier` does.
+
+mod common;
+
+use http_body_util::{BodyExt, Empty};
+use hyper::{body::Bytes, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use steam_zktls::{
+    disclosure,
+    notary::{self, NotaryAlg, NotaryEndpoint, TranscriptLimits},
+    ownership,
+    verify::{self, VerifiedPresentation},
+};
+use tls_core::{anchors::RootCertStore, key::Certificate, verify::WebPkiVerifier};
+use tlsn_common::config::ProtocolConfig;
+use tlsn_core::{
+    presentation::{Presentation, PresentationOutput},
+    request::RequestConfig,
+    transcript::TranscriptCommitConfig,
+    CryptoProvider,
+};
+use tlsn_prover::{Prover, ProverConfig};
+use tokio::net::TcpStream;
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+use common::{FakeSteam, API_KEY, FAKE_HOST, OWNER_STEAM_ID};
+
+const LIMITS: TranscriptLimits = TranscriptLimits {
+    max_sent_data: 1024,
+    max_recv_data: 4096,
+};
+
+/// Crypto provider that trusts the fake server's root certificate
+fn fake_steam_provider(server: &FakeSteam) -> CryptoProvider {
+    let mut root_store = RootCertStore::empty();
+    for der in rustls_pemfile::certs(&mut server.ca_pem().as_slice()).unwrap() {
+        root_store.add(&Certificate(der)).unwrap();
+    }
+
+    CryptoProvider {
+        cert: WebPkiVerifier::new(root_store, None),
+        ..Default::default()
+    }
+}
+
+/// Attest the owned-games response for `app_id` with `notary` and present it, committing and
+/// revealing exactly what the prover and `present` do
+async fn notarize(server: &FakeSteam, notary: &NotaryEndpoint, app_id: u32) -> Presentation {
+    let path = ownership::owned_games_path(API_KEY, OWNER_STEAM_ID, app_id).unwrap();
+
+    let config = ProverConfig::builder()
+        .server_name(FAKE_HOST)
+        .crypto_provider(fake_steam_provider(server))
+        .protocol_config(
+            ProtocolConfig::builder()
+                .max_sent_data(LIMITS.max_sent_data)
+                .max_recv_data(LIMITS.max_recv_data)
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+
+    let notary_connection = notary::connect(notary, LIMITS).await.unwrap();
+    let prover = Prover::new(config).setup(notary_connection.compat()).await.unwrap();
+
+    let socket = TcpStream::connect(server.addr).await.unwrap();
+    let (connection, prover_fut) = prover.connect(socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, http) = hyper::client::conn::http1::handshake(TokioIo::new(connection.compat()))
+        .await
+        .unwrap();
+    tokio::spawn(http);
+
+    let request = Request::builder()
+        .method("GET")
+        .uri(path)
+        .header("Host", FAKE_HOST)
+        .header("Accept", "application/json")
+        .header("Connection", "close")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    let response = request_sender.send_request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.into_body().collect().await.unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    let commit = disclosure::ownership_disclosure(prover.transcript().sent(), prover.transcript().received(), &[])
+        .unwrap();
+    let mut builder = TranscriptCommitConfig::builder(prover.transcript());
+    for range in &commit.sent {
+        builder.commit_sent(range).unwrap();
+    }
+    for range in &commit.recv {
+        builder.commit_recv(range).unwrap();
+    }
+    prover.transcript_commit(builder.build().unwrap());
+
+    let (attestation, secrets) = prover.finalize(&RequestConfig::default()).await.unwrap();
+
+    let reveal =
+        disclosure::ownership_disclosure(secrets.transcript().sent(), secrets.transcript().received(), &[]).unwrap();
+    let mut builder = secrets.transcript_proof_builder();
+    for range in &reveal.sent {
+        builder.reveal_sent(range).unwrap();
+    }
+    for range in &reveal.recv {
+        builder.reveal_recv(range).unwrap();
+    }
+    let transcript_proof = builder.build().unwrap();
+
+    let provider = CryptoProvider::default();
+    let mut presentation = attestation.presentation_builder(&provider);
+    presentation
+        .identity_proof(secrets.identity_proof())
+        .transcript_proof(transcript_proof);
+    presentation.build().unwrap()
+}
+
+async fn notarize_and_verify(alg: NotaryAlg, app_id: u32) -> (VerifiedPresentation, String) {
+    let server = FakeSteam::start();
+    let signing_key = notary::random_signing_key(alg);
+    let public_key = notary::public_key_hex(&signing_key, alg).unwrap();
+    let endpoint = NotaryEndpoint::Embedded { signing_key, alg };
+
+    let presentation = notarize(&server, &endpoint, app_id).await;
+    let provider = fake_steam_provider(&server);
+
+    // What any holder of the presentation can read of the request
+    let copy: Presentation = bincode::deserialize(&bincode::serialize(&presentation).unwrap()).unwrap();
+    let PresentationOutput { transcript, .. } = copy.verify(&provider).unwrap();
+    assert_hidden(transcript.unwrap().sent_unsafe());
+
+    let verified = verify::verify_presentation(presentation, &provider, FAKE_HOST, app_id).unwrap();
+
+    (verified, public_key)
+}
+
+/// The revealed request shows the free-game settings but neither the key nor the Steam ID, raw
+/// or percent-encoded: the low digit of every escape in their values is left out
+fn assert_hidden(sent: &[u8]) {
+    let request = String::from_utf8_lossy(sent);
+    assert!(request.contains("include_free_sub=0&include_played_free_games=0"), "{}", request);
+
+    for param in ownership::HIDDEN_PARAMS {
+        let start = request.find(&format!("&{}=", param.name)).unwrap() + param.name.len() + 2;
+        for escape in sent[start..start + 3 * param.len].chunks_exact(3) {
+            assert_eq!(escape[0], b'%', "{}", request);
+            assert_eq!(escape[2], 0, "low digit of {} is revealed", param.name);
+        }
+    }
+
+    for secret in [API_KEY, OWNER_STEAM_ID] {
+        for form in [secret.to_string(), ownership::percent_encode(secret)] {
+            assert!(!sent.windows(form.len()).any(|window| window == form.as_bytes()), "{} is revealed", form);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn owned_app_is_attested() {
+    let (verified, public_key) = notarize_and_verify(NotaryAlg::Secp256k1, 730).await;
+
+    assert!(verified.result.owns_game);
+    assert_eq!(verified.server_name, FAKE_HOST);
+    assert_eq!(hex::encode(&verified.attestation.body.verifying_key().data), public_key);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn app_not_owned_is_attested() {
+    let (verified, _) = notarize_and_verify(NotaryAlg::Secp256k1eth, 1245620).await;
+
+    assert!(!verified.result.owns_game);
+}
+
+#[test]
+fn inline_hex_signing_key_is_accepted() {
+    let key = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    assert_eq!(notary::load_signing_key(key, NotaryAlg::Secp256k1).unwrap(), hex::decode(key).unwrap());
+    assert!(notary::load_signing_key(&format!("0x{}", key), NotaryAlg::Secp256k1).is_ok());
+}
+
+#[test]
+fn missing_signing_key_file_is_reported_as_such() {
+    let err = notary::load_signing_key("keys/notary/missing.key", NotaryAlg::Secp256k1).unwrap_err();
+
+    assert!(err.to_string().contains("not found"), "{}", err);
+}
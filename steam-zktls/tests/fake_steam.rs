@@ -0,0 +1,75 @@
+//! `fake-steam` answers the owned-games query the prover sends with the outcomes a proof is
+//! made of: `game_count:1` for an owned app, `game_count:0` for one that is not owned.
+
+mod common;
+
+use serde_json::Value;
+
+use common::{FakeSteam, API_KEY, FAKE_HOST, OWNER_STEAM_ID, PRIVATE_STEAM_ID};
+
+async fn get(server: &FakeSteam, path_and_query: &str) -> reqwest::Response {
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(&server.ca_pem()).unwrap())
+        .resolve(FAKE_HOST, server.addr)
+        .build()
+        .unwrap();
+
+    client
+        .get(format!("https://{}:{}{}", FAKE_HOST, server.addr.port(), path_and_query))
+        .send()
+        .await
+        .unwrap()
+}
+
+/// The `response` object of a filtered `GetOwnedGames` call
+async fn owned_games(server: &FakeSteam, steam_id: &str, app_id: u32) -> Value {
+    let response = get(
+        server,
+        &format!(
+            "/IPlayerService/GetOwnedGames/v1/?key={}&steamid={}&appids_filter%5B0%5D={}&format=json\
+             &include_free_sub=0&include_played_free_games=0",
+            API_KEY, steam_id, app_id
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let body: Value = response.json().await.unwrap();
+    body["response"].clone()
+}
+
+#[tokio::test]
+async fn owned_app_is_listed() {
+    let server = FakeSteam::start();
+
+    let response = owned_games(&server, OWNER_STEAM_ID, 730).await;
+    assert_eq!(response["game_count"], 1);
+    assert_eq!(response["games"][0]["appid"], 730);
+}
+
+#[tokio::test]
+async fn app_not_owned_has_zero_count() {
+    let server = FakeSteam::start();
+
+    let response = owned_games(&server, OWNER_STEAM_ID, 1245620).await;
+    assert_eq!(response["game_count"], 0);
+    assert!(response.get("games").is_none());
+}
+
+#[tokio::test]
+async fn private_profile_reveals_no_count() {
+    let server = FakeSteam::start();
+
+    let response = owned_games(&server, PRIVATE_STEAM_ID, 730).await;
+    assert!(response.get("game_count").is_none());
+}
+
+#[tokio::test]
+async fn vanity_url_resolves_to_fixture_user() {
+    let server = FakeSteam::start();
+
+    let response = get(&server, &format!("/ISteamUser/ResolveVanityURL/v1/?key={}&vanityurl=ohnoitspanda", API_KEY)).await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["response"]["success"], 1);
+    assert_eq!(body["response"]["steamid"], OWNER_STEAM_ID);
+}